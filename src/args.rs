@@ -1,87 +1,462 @@
-use std::{path::PathBuf, time::Duration};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
-use clap::{Parser, builder::ValueHint};
+use clap::{Parser, Subcommand, builder::ValueHint};
 use clap_verbosity_flag::{Verbosity, WarnLevel};
-use jiff::civil::Date;
+use url::Url;
+
+use crate::{
+    FeedAttrs,
+    feedfetcher::{ProxySetting, parse_byte_size},
+    limits::MAX_CONCURRENT_FETCHES,
+    target::Target,
+    window::DateBound,
+};
 
 const AFTER_LONG_HELP: &str = "\
 Examples:
   Render the three most recent articles across a blogroll:
       openring -S urls.txt -t in.html > webring.html
 
+  Keep the options in openring.toml, found in the working directory:
+      num_articles = 5
+      template_file = \"in.html\"
+
+      [[feed]]
+      url = \"https://daily.example/feed.xml\"
+      weight = 7
+
   Down-weight a daily blog so it competes like a weekly one (urls.txt):
       https://quiet.example/feed.xml
       https://daily.example/feed.xml 7
 
   The same weight syntax works inline:
       openring -s 'https://daily.example/feed.xml 7' -t in.html
+
+  Attach per-feed attributes after the URL (urls.txt):
+      https://blog.example/rss.xml title=\"Alice's blog\" per_source=2 tags=rust,systems
+
+  Render a footer to stdout and a sidebar to a file from one fetch:
+      openring -S urls.txt -t footer.html --target 'sidebar.html out/sidebar.html num_articles=5'
+
+  Render only the feeds tagged rust:
+      openring -S urls.txt -t in.html --tag rust
+
+  Validate the feed sources and templates in CI without fetching anything:
+      openring check -S urls.txt -t in.html
+
+  Fetch every feed and report on each, failing if more than two are broken:
+      openring check -S urls.txt -t in.html --fetch --max-failures 2
+
+  Print the feed set every source resolves to, as a urls file:
+      openring feeds -c openring.toml
+
+  Keep the cache in the workspace, e.g. to save it between CI runs:
+      OPENRING_CACHE=.cache/openring.json openring -S urls.txt -t in.html
+
+  Render from the cache alone, e.g. on a plane:
+      openring -S urls.txt -t in.html --offline
+
+  See which feeds are waiting out a 429, then fetch them again anyway:
+      openring cache list
+      openring cache clear-gates
+
+  Leave out articles older than six months, so quiet blogs drop off:
+      openring -S urls.txt -t in.html --max-age 180d
 ";
 
 #[derive(Parser, Debug, Default)]
 #[command(author, version, about, long_about = None, after_long_help = AFTER_LONG_HELP)]
 pub struct Args {
+    // None renders, so invocations from before subcommands existed keep
+    // working unchanged.
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// Total number of articles to fetch
-    #[arg(short, long, default_value_t = 3)]
+    #[arg(short, long, default_value_t = 3, global = true)]
     pub num_articles: usize,
     /// Number of most recent articles to get from each feed
-    #[arg(short, long, default_value_t = 1)]
+    #[arg(short, long, default_value_t = 1, global = true)]
     pub per_source: usize,
-    /// File with URLs of Atom/RSS feeds to read (one URL per line, optionally followed by an integer weight; see --help)
+    /// File with URLs of Atom/RSS feeds to read (one URL per line, optionally followed by an integer weight and attributes; see --help)
+    ///
+    /// Each line is `URL [WEIGHT] [KEY=VALUE]...`; blank lines and lines starting with '#' or
+    /// "//" are ignored. Values containing spaces go in double quotes. A line `include <path>`
+    /// reads another urls file, resolved relative to the including file.
+    ///
+    /// An http(s) URL instead of a path downloads the list, as plain text or OPML. It is cached
+    /// like a feed, and the cached copy is used when the download fails.
+    ///
+    /// A listed URL may be a site's homepage rather than its feed: the feed the page advertises
+    /// with `<link rel="alternate">` is used, and remembered in the cache.
     ///
-    /// Each line is `URL [WEIGHT]`; blank lines and lines starting with '#' or "//" are ignored.
     /// A feed with weight N contributes a random pick from its N newest articles instead of
     /// always its newest, which keeps prolific feeds from dominating the ring: weight 7 roughly
     /// treats a daily blog like a weekly one. A feed with fewer than N recent articles sits out
     /// proportionally often instead. Listing one feed with two different weights is an error.
-    #[arg(short = 'S', long, value_name = "FILE", value_hint=ValueHint::FilePath)]
+    ///
+    /// Attributes are `weight=N` (the same as the bare weight), `title` and `homepage` (shown
+    /// instead of the ones the feed declares), `per_source` (overrides --per-source for this
+    /// feed), `min_interval` (overrides --min-interval for this feed), `proxy` (overrides --proxy
+    /// for this feed), and `tags` (a comma-separated list) and `note`, which the template sees as
+    /// `article.tags` and `article.note`.
+    #[arg(short = 'S', long, value_name = "FILE", value_hint=ValueHint::FilePath, global = true)]
     pub url_file: Option<PathBuf>,
+    /// OPML file of feeds to read, e.g. a blogroll exported from a feed reader
+    ///
+    /// Every `<outline>` with an `xmlUrl` attribute is a feed. An optional `weight` attribute
+    /// works like the weight after a URL in the urls file. Feeds listed here merge with those
+    /// given by --url and --url-file.
+    #[arg(long, value_name = "FILE", value_hint=ValueHint::FilePath, global = true)]
+    pub opml: Option<PathBuf>,
+    /// Also write every configured feed to this file as an OPML blogroll
+    ///
+    /// Each outline carries the feed's title and homepage as fetched this run, plus its weight,
+    /// so the file can be published for others to subscribe to or read back with --opml.
+    #[arg(long, value_name = "FILE", value_hint=ValueHint::FilePath, global = true)]
+    pub export_opml: Option<PathBuf>,
     /// Tera template file
-    #[arg(short, long, value_parser, value_name = "FILE", value_hint=ValueHint::FilePath)]
-    pub template_file: PathBuf,
+    #[arg(short, long, value_parser, value_name = "FILE", value_hint=ValueHint::FilePath, global = true)]
+    pub template_file: Option<PathBuf>,
+    /// Also render a template to a file, e.g. `sidebar.html out/sidebar.html num_articles=5` (can be repeated)
+    ///
+    /// Each value is `TEMPLATE OUTPUT [num_articles=N] [per_source=N]`, with `-` as OUTPUT for
+    /// stdout and the caps defaulting to --num-articles and --per-source. Feeds are fetched once
+    /// and every target renders from the same fetch. -t, if given, still renders to stdout.
+    #[arg(long, value_name = "TARGET", global = true)]
+    pub target: Vec<String>,
     /// A single URL to consider, optionally followed by a weight, e.g. `https://example.com/feed.xml 7` (can be repeated to specify multiple)
     ///
-    /// Accepts the same `URL [WEIGHT]` syntax as the urls file; see --url-file for what
-    /// weights do.
+    /// Accepts the same `URL [WEIGHT] [KEY=VALUE]...` syntax as the urls file; see --url-file
+    /// for what weights and attributes do.
     // Raw strings on purpose: parsing happens in FeedSet::resolve, where errors carry
     // span diagnostics pointing at the offending token. clap's error channel cannot
     // transport a miette report.
-    #[arg(short = 's', long, value_hint=ValueHint::Url)]
+    #[arg(short = 's', long, value_hint=ValueHint::Url, global = true)]
     pub url: Vec<String>,
-    /// Only include articles before this date (in YYYY-MM-DD format).
+    /// Only use feeds carrying this tag (can be repeated to accept any of several)
+    ///
+    /// Tags come from the `tags` attribute of a feed listing. Without --tag every feed is used.
+    /// Templates also see `articles_by_tag`, which groups the articles of tagged feeds by tag.
+    #[arg(long, value_name = "TAG", global = true)]
+    pub tag: Vec<String>,
+    /// Only include articles before this date (YYYY-MM-DD) or RFC 3339 timestamp
+    ///
+    /// A date starts at midnight in the system timezone, so articles close to the boundary may
+    /// land on either side depending on where the build runs; a timestamp with an offset, e.g.
+    /// 2024-01-31T00:00:00Z, is exact. In addition, some feeds are truncated, and may have
+    /// already pruned away articles before this date from the feed itself.
+    #[arg(short, long, value_name = "DATE", global = true)]
+    pub before: Option<DateBound>,
+    /// Only include articles published at or after this date (YYYY-MM-DD) or RFC 3339 timestamp
     ///
-    /// This is naive (no timezone), so articles close to the boundary in different timezones might
-    /// be unexpectedly filtered. In addition, some feeds are truncated, and may have already pruned
-    /// away articles before this date from the feed itself.
-    #[arg(short, long)]
-    pub before: Option<Date>,
+    /// Read like --before. Together they form a window that includes its start and excludes its
+    /// end.
+    #[arg(long, value_name = "DATE", global = true)]
+    pub after: Option<DateBound>,
+    /// Only include articles published within this duration of now, e.g. 180d
+    ///
+    /// Keeps a long-quiet blog from making the ring look abandoned. With --after as well, the
+    /// later of the two bounds applies.
+    #[arg(long, value_parser = humantime::parse_duration, value_name = "DURATION", global = true)]
+    pub max_age: Option<Duration>,
     /// Do NOT use request cache stored on disk.
     ///
-    /// Note that the cache only prevents refetching if the feed source responds
-    /// with a 429. In this case, we respect Retry-After, or default to 4h.
-    /// Otherwise, the existence of a cache file just allows openring to respect
-    /// `ETag` and `Last-Modified` headers for conditional requests.
-    #[arg(long)]
+    /// The cache skips a feed's request while the feed is still fresh, was polled within
+    /// --min-interval, or is inside a 429's Retry-After window (4h by default). Otherwise it
+    /// supplies the `ETag` and `Last-Modified` headers for a conditional request, and it serves
+    /// the stale copy when a fetch fails transiently (see --stale-if-error).
+    #[arg(long, global = true)]
     pub no_cache: bool,
+    /// Serve every feed from the cache without touching the network
+    ///
+    /// Cached copies are used however old they are, regardless of --max-cache-age. Feeds with no
+    /// cached copy are reported and left out, and articles without a summary keep none unless one
+    /// derived from the article's page on an earlier run is cached.
+    #[arg(long, conflicts_with = "no_cache", global = true)]
+    pub offline: bool,
+    /// Send a request for every feed, even ones whose cached copy is still fresh
+    ///
+    /// By default a feed is not requested again until the freshness its server stated with
+    /// `Cache-Control: max-age` or `Expires`, or the feed with RSS `<ttl>` or
+    /// `sy:updatePeriod`, runs out. This still sends conditional requests, so unchanged feeds
+    /// cost a 304.
+    #[arg(long, conflicts_with = "offline", global = true)]
+    pub revalidate: bool,
+    #[command(flatten)]
+    pub negations: Negations,
+    /// Keep the request cache in this file instead of the platform's cache directory
+    ///
+    /// Lets a CI runner or sandboxed build keep the cache in its workspace and save it as an
+    /// artifact. Missing parent directories are created on the first write.
+    #[arg(
+        long,
+        env = "OPENRING_CACHE",
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        global = true
+    )]
+    pub cache_file: Option<PathBuf>,
     /// Discard all cached requests older than this duration
     #[arg(
         long,
         value_parser = humantime::parse_duration,
-        default_value = "30d"
+        default_value = "30d",
+        global = true
     )]
     pub max_cache_age: Duration,
+    /// Serve a cached copy up to this old when a fetch fails in transit or with a 5xx
+    ///
+    /// A feed whose server is down or unreachable keeps its posts in the output instead of
+    /// dropping out; the run logs a warning and `check --fetch` marks the feed stale. Errors a
+    /// retry cannot fix, like a 404 or a malformed feed, still fail. Entries older than
+    /// --max-cache-age are gone from the cache either way; "0s" turns this off.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "7d",
+        global = true
+    )]
+    pub stale_if_error: Duration,
+    /// Poll each feed at most once per this interval, however often the site is rebuilt
+    ///
+    /// A feed polled more recently is served from the cache without a request, even with
+    /// --revalidate. A feed's `min_interval` attribute overrides this for that feed alone. Keep
+    /// it under --max-cache-age, or feeds age out of the cache before they are due.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "0s",
+        global = true
+    )]
+    pub min_interval: Duration,
+    /// Retry a fetch that fails transiently up to this many times
+    ///
+    /// Failed connections and TLS handshakes, timeouts, connection resets, and 502, 503, and 504
+    /// responses are retried after an exponential backoff with jitter, starting around half a
+    /// second. Other failures, and every 4xx, are never retried. Applies to feeds, a remote
+    /// urls file, and the article pages summaries are derived from.
+    #[arg(long, value_name = "N", default_value_t = 2, global = true)]
+    pub retries: u32,
+    /// The time budget for the run's fetches: no request runs and no retry waits past it; 0s lifts it
+    ///
+    /// Counted from the start of the run. A request still running when it is spent is cut off,
+    /// and failed fetches fail (or fall back to --stale-if-error) at once instead of being
+    /// retried.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "5m",
+        global = true
+    )]
+    pub time_budget: Duration,
+    /// Send at most this many requests at once to any one host; 0 lifts the cap
+    ///
+    /// Rings often hold several blogs on one host, like a shared Mastodon instance, and a burst
+    /// of simultaneous requests can get them all rate limited. Hosts are compared by full name,
+    /// so `a.github.io` and `b.github.io` count separately. Covers feeds, a remote urls file,
+    /// and article pages alike, on top of --max-concurrent.
+    #[arg(long, value_name = "N", default_value_t = 4, global = true)]
+    pub max_per_host: usize,
+    /// Start requests to the same host at least this far apart, e.g. 1s
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "0s",
+        global = true
+    )]
+    pub host_delay: Duration,
+    /// Send at most this many requests at once in all; 0 lifts the cap
+    ///
+    /// The default keeps a long urls file from exhausting the process's file descriptors (macOS
+    /// allows 256 per process) while keeping the network busy.
+    #[arg(long, value_name = "N", default_value_t = MAX_CONCURRENT_FETCHES, global = true)]
+    pub max_concurrent: usize,
+    /// Give up on a server that cannot complete a TCP and TLS handshake within this long; 0s waits forever
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "10s",
+        global = true
+    )]
+    pub connect_timeout: Duration,
+    /// Give up on a transfer that goes this long without receiving a single byte; 0s waits forever
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "30s",
+        global = true
+    )]
+    pub read_timeout: Duration,
+    /// Give up on any request still running after this long, however steadily it trickles in; 0s waits forever
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        default_value = "5m",
+        global = true
+    )]
+    pub request_timeout: Duration,
+    /// Append this to the user agent, e.g. `(+https://example.com/blogroll)`
+    ///
+    /// Requests identify themselves as `openring/VERSION`, followed by this text. A URL in it
+    /// tells feed operators where the requests come from; some hosts block bare tool names.
+    #[arg(long, value_name = "TEXT", global = true)]
+    pub user_agent_suffix: Option<String>,
+    /// Send this email address or URL in the `From` header of every request
+    ///
+    /// Lets feed operators who ask crawlers for contact details reach whoever runs the ring.
+    #[arg(long, value_name = "CONTACT", global = true)]
+    pub contact: Option<String>,
+    /// Reject a feed or urls file larger than this, e.g. 16MiB; 0 lifts the cap
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_byte_size,
+        default_value = "64MiB",
+        global = true
+    )]
+    pub max_feed_size: u64,
+    /// Derive no summary from an article page larger than this, e.g. 2MiB; 0 lifts the cap
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_byte_size,
+        default_value = "8MiB",
+        global = true
+    )]
+    pub max_page_size: u64,
+    /// Send requests through this proxy, e.g. `socks5h://127.0.0.1:9050` for Tor; `none` goes direct
+    ///
+    /// Takes an http, https, socks4, socks4a, socks5, or socks5h URL, with credentials in it if
+    /// the proxy wants them. Hosts in `NO_PROXY` still go direct. Unset, requests follow the
+    /// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`, and `NO_PROXY` environment variables. A feed's
+    /// `proxy` attribute overrides this for that feed and its article pages.
+    #[arg(
+        long,
+        env = "OPENRING_PROXY",
+        hide_env_values = true,
+        value_name = "URL",
+        global = true
+    )]
+    pub proxy: Option<ProxySetting>,
+    /// Trust the PEM certificates in this file as well as the system's root certificates
+    ///
+    /// For feeds behind a corporate TLS-intercepting proxy or served with a private CA.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, global = true)]
+    pub ca_file: Option<PathBuf>,
     /// Seed the random selection used by weighted feeds, for reproducible output
     ///
     /// Has no effect unless at least one feed has a weight. By default every run draws fresh
     /// entropy so weighted feeds rotate; a fixed seed (e.g. --seed "$(date +%Y%m%d)") keeps
     /// the output stable for a period of your choosing.
-    #[arg(long, value_name = "U64")]
+    #[arg(long, value_name = "U64", global = true)]
     pub seed: Option<u64>,
+    /// TOML config file supplying defaults for these options and a `[[feed]]` table per feed
+    ///
+    /// Defaults to `openring.toml` in the working directory, if there is one. Flags given on the
+    /// command line override the file, and relative paths in the file resolve against its
+    /// directory.
+    #[arg(short, long, value_name = "FILE", value_hint=ValueHint::FilePath, global = true)]
+    pub config: Option<PathBuf>,
+    /// The `[[feed]]` tables of the config file, merged with the other feed sources.
+    #[arg(skip)]
+    pub(crate) config_feeds: HashMap<Url, FeedAttrs>,
+    /// The `[[target]]` tables of the config file, used unless --target is given.
+    #[arg(skip)]
+    pub(crate) config_targets: Vec<Target>,
     // WarnLevel: warnings are actionable (skipped entries, cache failures,
     // redirected feeds) and must not require -v; -q silences them.
     #[clap(flatten)]
     pub verbose: Verbosity<WarnLevel>,
 }
 
+/// The subcommands. Options are shared: each takes the ones it needs and
+/// ignores the rest, so they can be given before or after the subcommand.
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Fetch the feeds and render the templates (the default)
+    Render,
+    /// Validate the config, feed sources, and templates without fetching feeds or rendering
+    ///
+    /// Reports every problem `render` would stop at before its first feed fetch, so a CI job can
+    /// catch a typo in the urls file or a template syntax error in milliseconds.
+    Check(CheckArgs),
+    /// Show where the request cache lives and how many entries it holds, or inspect and edit it
+    Cache(CacheArgs),
+    /// List the resolved feed set, one urls-file line per feed
+    ///
+    /// The listing merges every feed source (-s, -S, --opml, and the config file), applies
+    /// --tag, and prints each feed with its attributes, so the output is itself a valid urls
+    /// file.
+    Feeds,
+}
+
+/// Flags that turn off a switch the config file turns on. Only their
+/// presence on the command line matters, which the config layering reads
+/// from the matches.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Negations {
+    /// Use the request cache despite `no_cache = true` in the config file
+    #[arg(long, overrides_with = "no_cache", global = true)]
+    pub use_cache: bool,
+    /// Use the network despite `offline = true` in the config file
+    #[arg(long, overrides_with = "offline", global = true)]
+    pub online: bool,
+}
+
+/// Options of `cache`.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheArgs {
+    // None summarizes the cache file, as `cache` did before it had actions.
+    #[command(subcommand)]
+    pub action: Option<CacheAction>,
+}
+
+/// What `cache` does to the request cache. The actions that edit it hold the
+/// file's lock from read to write, so a concurrent run cannot interleave.
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum CacheAction {
+    /// List every entry with when it was fetched, its validators, and any open 429 window
+    List,
+    /// Print the cached body of a URL, byte for byte
+    Show { url: Url },
+    /// Remove a URL from the cache, so the next run fetches it unconditionally
+    Evict { url: Url },
+    /// Forget every 429 retry window, so the next run fetches rate-limited feeds again
+    ClearGates,
+    /// Remove the entries fetched longer ago than a duration
+    Prune {
+        /// Age past which entries are removed [default: --max-cache-age]
+        #[arg(long, value_parser = humantime::parse_duration, value_name = "DURATION")]
+        older_than: Option<Duration>,
+    },
+}
+
+/// Options of `check`.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckArgs {
+    /// Also fetch every feed and print a per-feed health report
+    ///
+    /// The report gives each feed's HTTP status, whether the cache answered, how many entries it
+    /// holds and how many `render` would skip, the date of its newest usable entry, and any
+    /// redirect or discovered feed. A feed fails if it cannot be fetched or parsed, or has no
+    /// usable entries.
+    #[arg(long)]
+    pub fetch: bool,
+    /// Number of failed feeds to tolerate before exiting with an error
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "fetch")]
+    pub max_failures: usize,
+    /// Also fail feeds whose newest usable entry is older than this duration, e.g. 365d
+    #[arg(long, value_parser = humantime::parse_duration, value_name = "DURATION", requires = "fetch")]
+    pub stale_after: Option<Duration>,
+}
+
 #[cfg(test)]
 mod test {
     use crate::*;
@@ -91,6 +466,18 @@ mod test {
         Args::command().debug_assert();
     }
 
+    #[test]
+    fn help_never_shows_the_proxy_from_the_environment() {
+        use clap::CommandFactory;
+        // The proxy URL may carry a password.
+        let command = Args::command();
+        let proxy = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "proxy")
+            .unwrap();
+        assert!(proxy.is_hide_env_values_set());
+    }
+
     #[test]
     fn url_arg_passes_inline_weights_through_to_run() {
         use clap::Parser;
@@ -108,6 +495,50 @@ mod test {
         assert_eq!(args.seed, None);
     }
 
+    #[test]
+    fn options_work_before_and_after_a_subcommand() {
+        use clap::Parser;
+
+        // Invocations from before subcommands existed still render.
+        let args = Args::try_parse_from(["openring", "-S", "urls.txt", "-t", "in.html"]).unwrap();
+        assert_eq!(args.command, None);
+
+        for argv in [
+            ["openring", "feeds", "-S", "urls.txt"],
+            ["openring", "-S", "urls.txt", "feeds"],
+        ] {
+            let args = Args::try_parse_from(argv).unwrap();
+            assert_eq!(args.command, Some(Command::Feeds));
+            assert_eq!(args.url_file, Some("urls.txt".into()));
+        }
+    }
+
+    #[test]
+    fn cache_takes_an_optional_action() {
+        use std::time::Duration;
+
+        use clap::Parser;
+
+        use super::{CacheAction, CacheArgs};
+
+        let args = Args::try_parse_from(["openring", "cache"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Cache(CacheArgs { action: None }))
+        );
+
+        let args =
+            Args::try_parse_from(["openring", "cache", "prune", "--older-than", "7d"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Cache(CacheArgs {
+                action: Some(CacheAction::Prune {
+                    older_than: Some(Duration::from_hours(7 * 24))
+                })
+            }))
+        );
+    }
+
     #[test]
     fn warnings_are_visible_by_default() {
         use tracing_log::AsTrace;
@@ -1,7 +1,8 @@
 use std::{
     cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
-    io::{BufReader, BufWriter, Write},
+    io::{self, BufReader, Read, Seek, Write},
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -10,10 +11,16 @@ use dashmap::DashMap;
 use directories::ProjectDirs;
 use jiff::{Span, Timestamp, ToSpan};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::{info, warn};
 use url::Url;
 
-use crate::{args::Args, error::Result};
+use crate::{
+    args::{Args, CacheAction},
+    error::{OpenringError, Result},
+    feedfetcher::logic::retry_after_gate_open,
+    table,
+};
 
 pub(crate) const MAX_SPAN_SEC: i64 = 631_107_417_600;
 
@@ -21,12 +28,30 @@ pub(crate) const MAX_SPAN_SEC: i64 = 631_107_417_600;
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum CachePath<'a> {
     Default,
-    // Only tests construct Path, so the variant is dead in non-test builds.
-    #[cfg_attr(not(test), expect(dead_code))]
     Path(&'a Path),
 }
 
-/// Describes a feed fetch result that can be serialized to disk
+impl<'a> CachePath<'a> {
+    /// The cache file `args` names with `--cache-file` (or `OPENRING_CACHE`
+    /// or the config file), else the platform default.
+    pub(crate) fn of(args: &'a Args) -> Self {
+        args.cache_file
+            .as_deref()
+            .map_or(CachePath::Default, CachePath::Path)
+    }
+
+    /// The cache file this names, if the platform has a cache directory.
+    fn resolve(self) -> Option<PathBuf> {
+        match self {
+            CachePath::Default => get_cache_path(),
+            CachePath::Path(p) => Some(p.to_path_buf()),
+        }
+    }
+}
+
+/// Describes a feed fetch result. On disk it is an [`IndexEntry`] plus a
+/// body file; this serialized form, body inline, is layout 1, which is still
+/// read so an old cache migrates in place.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) struct CacheValue {
     pub(crate) timestamp: Timestamp,
@@ -38,6 +63,20 @@ pub(crate) struct CacheValue {
     /// mojibake when the parser honors the XML prolog.
     #[serde(with = "body_base64")]
     pub(crate) body: Option<Vec<u8>>,
+    /// For a listed page that turned out to be HTML, the feed it
+    /// advertises, so later runs fetch the feed without the page. Absent in
+    /// caches written before feed autodiscovery, which load unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) discovered: Option<Url>,
+    /// Until when the publisher said the body stays current, by
+    /// `Cache-Control`, `Expires`, or the feed's own update hints. Until
+    /// then a fetch serves the body without sending a request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fresh_until: Option<Timestamp>,
+    /// For an article page rather than a feed, the summary derived from it
+    /// (see [`crate::summarize`]). The page itself is not kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) summary: Option<String>,
 }
 
 /// Serialize the body as base64: `serde_json` would otherwise render a byte
@@ -92,6 +131,9 @@ impl PartialEq for CacheValue {
             && self.last_modified == other.last_modified
             && self.etag == other.etag
             && self.body == other.body
+            && self.discovered == other.discovered
+            && self.fresh_until == other.fresh_until
+            && self.summary == other.summary
             && match (&self.retry_after, &other.retry_after) {
                 (Some(a), Some(b)) => spans_equal(a, b),
                 (None, None) => true,
@@ -103,6 +145,90 @@ impl Eq for CacheValue {}
 
 pub(crate) type Cache = DashMap<Url, CacheValue>;
 
+/// The on-disk layout `store` writes. Layout 1 was a single pretty-printed
+/// JSON map with every body inline, and carried no version.
+const STORE_VERSION: u32 = 2;
+
+/// The index file: every entry's metadata in compact JSON, with each body
+/// in a file of its own named by its SHA-256 (see [`body_dir`]). Entries are
+/// in URL order so an unchanged cache serializes to the same bytes.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Index {
+    version: u32,
+    entries: BTreeMap<Url, IndexEntry>,
+}
+
+/// A [`CacheValue`] as the index records it, with the body replaced by the
+/// name of its file.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct IndexEntry {
+    timestamp: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_after: Option<Span>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    discovered: Option<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fresh_until: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+/// What an index file may hold. A layout-1 cache is a bare map keyed by
+/// URL, which never has the `version` key every index has, so the two
+/// cannot be mistaken for each other.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OnDisk {
+    Indexed(Index),
+    /// Loaded as is; the next store writes it back as an index, which is the
+    /// whole migration.
+    Inline(HashMap<Url, CacheValue>),
+}
+
+/// The directory holding the bodies of the index at `index`: beside it,
+/// named after its full file name, so two caches in one directory never
+/// share bodies.
+fn body_dir(index: &Path) -> PathBuf {
+    let mut dir = index.as_os_str().to_owned();
+    dir.push(".bodies");
+    dir.into()
+}
+
+/// The file name of `body`: its SHA-256, in hex.
+fn body_name(body: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    let mut name = String::with_capacity(64);
+    for byte in Sha256::digest(body) {
+        // Writing to a String cannot fail.
+        let _ = write!(name, "{byte:02x}");
+    }
+    name
+}
+
+/// Store `body` in `dir` under its content hash, unless an identical body is
+/// already there, and return the file name.
+fn write_body(dir: &Path, body: &[u8]) -> Result<String> {
+    let name = body_name(body);
+    let path = dir.join(&name);
+    if !path.exists() {
+        // Written aside and renamed into place, so a crash never leaves a
+        // truncated body under a name its content does not hash to.
+        let partial = dir.join(format!("{name}.partial"));
+        fs::write(&partial, body)?;
+        fs::rename(&partial, &path)?;
+    }
+    Ok(name)
+}
+
 pub(crate) trait StoreExt {
     /// Store the cache under the given path. Update access timestamps
     fn store<T: AsRef<Path>>(&self, path: T) -> Result<()>;
@@ -122,53 +248,194 @@ impl StoreExt for Cache {
         // exclusive lock is held, or concurrent readers holding the shared
         // lock would see their data vanish mid-read.
         let f = fs::OpenOptions::new()
+            .read(true)
             .write(true)
             .create(true)
             .truncate(false)
             .open(path)?;
         f.lock()?;
-        f.set_len(0)?;
-        let mut w = BufWriter::new(f);
-        serde_json::to_writer_pretty(&mut w, self)?;
-        // BufWriter's Drop swallows write errors; flush so they surface.
-        w.flush()?;
-        Ok(())
+        write_locked(self, &f, path)
     }
 
     fn load<T: AsRef<Path>>(path: T, max_age_secs: u64, now: Timestamp) -> Result<Cache> {
-        let clamped_secs: i64 = max_age_secs.min(MAX_SPAN_SEC as u64).cast_signed();
-
+        let path = path.as_ref();
         let f = fs::File::open(path)?;
 
         // Acquire a shared lock so multiple readers can coexist, but no writers
         f.lock_shared()?;
 
-        let r = BufReader::new(f);
+        read_locked(&f, path, max_age_secs, now)
+    }
+}
 
-        let map: DashMap<Url, CacheValue> = serde_json::from_reader(r)?;
+/// Read the cache whose index is `f`, open at `path`, keeping the entries
+/// younger than `max_age_secs` as of `now`. The caller must hold a lock on
+/// `f`: bodies are only consistent with the index under it.
+///
+/// Only the index is parsed in full; the body of an expired entry is never
+/// read.
+fn read_locked(f: &fs::File, path: &Path, max_age_secs: u64, now: Timestamp) -> Result<Cache> {
+    let clamped_secs: i64 = max_age_secs.min(MAX_SPAN_SEC as u64).cast_signed();
+    let threshold = clamped_secs.seconds();
+    let expired = |timestamp: Timestamp| {
+        (now - timestamp)
+            .compare(threshold)
+            .is_ok_and(|ordering| ordering != Ordering::Less)
+    };
 
-        // Remove entries older than max_age_secs
-        let current_ts = now;
-        let threshold = clamped_secs.seconds();
-        let keys_to_remove: Vec<Url> = map
-            .iter()
-            .filter_map(|entry| {
-                let v = entry.value();
-                if (current_ts - v.timestamp).compare(threshold).ok()? == std::cmp::Ordering::Less {
-                    None
-                } else {
-                    Some(entry.key().clone())
+    let cache = Cache::new();
+    match serde_json::from_reader(BufReader::new(f))? {
+        OnDisk::Inline(map) => {
+            for (url, value) in map {
+                if !expired(value.timestamp) {
+                    cache.insert(url, value);
                 }
-            })
-            .collect();
+            }
+        }
+        OnDisk::Indexed(index) => {
+            if index.version != STORE_VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "cache layout {} is not layout {STORE_VERSION}, the one this openring reads",
+                        index.version
+                    ),
+                )
+                .into());
+            }
+            let bodies = body_dir(path);
+            for (url, entry) in index.entries {
+                if expired(entry.timestamp) {
+                    continue;
+                }
+                let body = match &entry.body {
+                    Some(name) => match fs::read(bodies.join(name)) {
+                        Ok(body) => Some(body),
+                        // A lost body loses its entry, not the whole cache:
+                        // the feed is simply fetched afresh.
+                        Err(e) => {
+                            warn!(%url, "Cached body {name} is unreadable: {e}. Dropping the entry.");
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                cache.insert(
+                    url,
+                    CacheValue {
+                        timestamp: entry.timestamp,
+                        retry_after: entry.retry_after,
+                        last_modified: entry.last_modified,
+                        etag: entry.etag,
+                        body,
+                        discovered: entry.discovered,
+                        fresh_until: entry.fresh_until,
+                        summary: entry.summary,
+                    },
+                );
+            }
+        }
+    }
+    Ok(cache)
+}
+
+/// Write `cache` to the index `f`, open at `path`, and its bodies beside
+/// it. The caller must hold the exclusive lock on `f`.
+///
+/// Only what changed touches the disk: each distinct body is written once
+/// and then left alone, the index is rewritten only when its bytes differ,
+/// and bodies no entry refers to any more are removed.
+fn write_locked(cache: &Cache, f: &fs::File, path: &Path) -> Result<()> {
+    let bodies = body_dir(path);
+    fs::create_dir_all(&bodies)?;
+
+    let mut entries = BTreeMap::new();
+    for entry in cache {
+        let (url, value) = entry.pair();
+        let body = value
+            .body
+            .as_deref()
+            .map(|body| write_body(&bodies, body))
+            .transpose()?;
+        entries.insert(
+            url.clone(),
+            IndexEntry {
+                timestamp: value.timestamp,
+                retry_after: value.retry_after,
+                last_modified: value.last_modified.clone(),
+                etag: value.etag.clone(),
+                body,
+                discovered: value.discovered.clone(),
+                fresh_until: value.fresh_until,
+                summary: value.summary.clone(),
+            },
+        );
+    }
+    let index = Index {
+        version: STORE_VERSION,
+        entries,
+    };
+    let bytes = serde_json::to_vec(&index)?;
 
-        for k in keys_to_remove {
-            map.remove(&k);
+    let mut file = f;
+    let mut current = Vec::new();
+    file.rewind()?;
+    file.read_to_end(&mut current)?;
+    if current != bytes {
+        f.set_len(0)?;
+        file.rewind()?;
+        file.write_all(&bytes)?;
+    }
+
+    let referenced: HashSet<&str> = index
+        .entries
+        .values()
+        .filter_map(|entry| entry.body.as_deref())
+        .collect();
+    for file in fs::read_dir(&bodies)? {
+        let file = file?;
+        if !file
+            .file_name()
+            .to_str()
+            .is_some_and(|name| referenced.contains(name))
+        {
+            // Best-effort: a leftover body only costs disk space.
+            let _ = fs::remove_file(file.path());
         }
+    }
+    Ok(())
+}
+
+/// Apply `edit`, which returns how many entries it changed, to the cache at
+/// `path` and write the result back, all under one exclusive lock, so a run
+/// storing its cache meanwhile waits rather than having its write lost. An
+/// edit that changed nothing writes nothing. `None` if there is no cache
+/// file.
+fn modify(path: &Path, edit: impl FnOnce(&Cache) -> usize) -> Result<Option<usize>> {
+    let f = match fs::OpenOptions::new().read(true).write(true).open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    f.lock()?;
+    let cache = read_locked(&f, path, u64::MAX, Timestamp::now())?;
+    let changed = edit(&cache);
+    if changed > 0 {
+        write_locked(&cache, &f, path)?;
+    }
+    Ok(Some(changed))
+}
 
-        Ok(map)
+/// Every entry of the cache file at `path`, however old. `None` if there is
+/// no cache file.
+fn load_all(path: &Path, now: Timestamp) -> Result<Option<Cache>> {
+    match Cache::load(path, u64::MAX, now) {
+        Ok(cache) => Ok(Some(cache)),
+        Err(OpenringError::IoError(e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
     }
 }
+
 /// Persist the cache, best-effort.
 ///
 /// The cache is purely an optimization, so a failed write (read-only cache
@@ -178,12 +445,8 @@ pub(crate) fn store_cache(cache: &Cache, no_cache: bool, cache_path: CachePath)
     if no_cache {
         return;
     }
-    let path = match cache_path {
-        CachePath::Default => {
-            let Some(path) = get_cache_path() else { return };
-            path
-        }
-        CachePath::Path(p) => p.to_path_buf(),
+    let Some(path) = cache_path.resolve() else {
+        return;
     };
     if let Err(e) = cache.store(path) {
         warn!("Error while storing cache: {e}. Continuing without.");
@@ -198,11 +461,12 @@ pub(crate) fn load_cache(args: &Args, cache_path: CachePath) -> Option<Cache> {
     if args.no_cache {
         return None;
     }
-    let default_cache_path = get_cache_path();
-    let cache_path = match cache_path {
-        CachePath::Default if default_cache_path.is_none() => return None,
-        CachePath::Default => default_cache_path.unwrap(),
-        CachePath::Path(p) => p.to_path_buf(),
+    let cache_path = cache_path.resolve()?;
+    // Offline, a stale copy beats none, so nothing ages out.
+    let max_age = if args.offline {
+        Duration::MAX
+    } else {
+        args.max_cache_age
     };
 
     // Discard entire cache if it hasn't been updated since `max_cache_age`.
@@ -216,23 +480,23 @@ pub(crate) fn load_cache(args: &Args, cache_path: CachePath) -> Option<Cache> {
         Ok(metadata) => {
             let modified = metadata.modified().ok()?;
             let elapsed = modified.elapsed().ok()?;
-            if elapsed > args.max_cache_age {
+            if elapsed > max_age {
                 warn!(
                     "Cache is too old (age: {:#?}, max age: {:#?}). Discarding and recreating.",
                     Duration::from_secs(elapsed.as_secs()),
-                    Duration::from_secs(args.max_cache_age.as_secs())
+                    Duration::from_secs(max_age.as_secs())
                 );
                 return None;
             }
             info!(
                 "Cache is recent (age: {:#?}, max age: {:#?}). Using.",
                 Duration::from_secs(elapsed.as_secs()),
-                Duration::from_secs(args.max_cache_age.as_secs())
+                Duration::from_secs(max_age.as_secs())
             );
         }
     }
 
-    let cache = Cache::load(cache_path, args.max_cache_age.as_secs(), Timestamp::now());
+    let cache = Cache::load(cache_path, max_age.as_secs(), Timestamp::now());
     match cache {
         Ok(cache) => Some(cache),
         Err(e) => {
@@ -242,13 +506,219 @@ pub(crate) fn load_cache(args: &Args, cache_path: CachePath) -> Option<Cache> {
     }
 }
 
+/// Describe the cache file for `openring cache`: where it lives, how many
+/// entries it holds, and how long ago a run last wrote it.
+///
+/// # Errors
+///
+/// Returns an error if the cache file exists but cannot be read or parsed,
+/// or `out` cannot be written.
+pub(crate) fn report(cache_path: CachePath, mut out: impl Write) -> Result<()> {
+    let Some(path) = cache_path.resolve() else {
+        writeln!(
+            out,
+            "no cache directory on this platform; openring runs uncached"
+        )?;
+        return Ok(());
+    };
+    let metadata = match fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            writeln!(out, "{}: no cache yet", path.display())?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // Every entry, however old: this reports what is on disk, not what the
+    // next run would keep.
+    let cache = Cache::load(&path, u64::MAX, Timestamp::now())?;
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map_or_else(
+            || "unknown".to_string(),
+            |elapsed| {
+                humantime::format_duration(Duration::from_secs(elapsed.as_secs())).to_string()
+            },
+        );
+    writeln!(out, "{}", path.display())?;
+    let body_bytes: u64 = fs::read_dir(body_dir(&path))
+        .into_iter()
+        .flatten()
+        .filter_map(|file| file.ok()?.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    writeln!(
+        out,
+        "{} entries, {} bytes, written {age} ago",
+        cache.len(),
+        metadata.len() + body_bytes
+    )?;
+    Ok(())
+}
+
+/// Carry out `openring cache ACTION` on the cache file, or without an
+/// action, [`report`] on it. `max_cache_age` is the default age for `prune`.
+///
+/// # Errors
+///
+/// Returns an error if the cache file cannot be read, parsed, or written,
+/// `show` names a URL without a cached body, or `out` cannot be written.
+pub(crate) fn command(
+    action: Option<CacheAction>,
+    max_cache_age: Duration,
+    cache_path: CachePath,
+    mut out: impl Write,
+) -> Result<()> {
+    let Some(action) = action else {
+        return report(cache_path, out);
+    };
+    let Some(path) = cache_path.resolve() else {
+        writeln!(
+            out,
+            "no cache directory on this platform; openring runs uncached"
+        )?;
+        return Ok(());
+    };
+    let now = Timestamp::now();
+    let done = match action {
+        CacheAction::List => load_all(&path, now)?.map(|cache| list(&cache, now, &mut out)),
+        CacheAction::Show { url } => {
+            // No summary line here: the body alone goes to `out`, so it can
+            // be piped straight into a feed validator.
+            let body = load_all(&path, now)?
+                .and_then(|cache| cache.get(&url).and_then(|cv| cv.body.clone()))
+                .ok_or_else(|| OpenringError::NotCached(url.to_string()))?;
+            out.write_all(&body)?;
+            return Ok(());
+        }
+        CacheAction::Evict { url } => {
+            modify(&path, |cache| usize::from(cache.remove(&url).is_some()))?.map(|evicted| {
+                if evicted > 0 {
+                    writeln!(out, "evicted {url}")
+                } else {
+                    writeln!(out, "{url} is not cached")
+                }
+            })
+        }
+        CacheAction::ClearGates => modify(&path, |cache| {
+            cache
+                .iter_mut()
+                .filter_map(|mut entry| entry.retry_after.take())
+                .count()
+        })?
+        .map(|cleared| writeln!(out, "cleared {cleared} 429 retry windows")),
+        CacheAction::Prune { older_than } => {
+            let older_than = older_than.unwrap_or(max_cache_age);
+            // The same cutoff `Cache::load` applies with --max-cache-age.
+            let cutoff = now.checked_sub(older_than).unwrap_or(Timestamp::MIN);
+            modify(&path, |cache| {
+                let before = cache.len();
+                cache.retain(|_, cv| cv.timestamp > cutoff);
+                before - cache.len()
+            })?
+            .map(|pruned| {
+                writeln!(
+                    out,
+                    "pruned {pruned} entries fetched more than {} ago",
+                    humantime::format_duration(older_than)
+                )
+            })
+        }
+    };
+    match done {
+        Some(written) => written?,
+        None => writeln!(out, "{}: no cache yet", path.display())?,
+    }
+    Ok(())
+}
+
+/// Write one row per entry of `cache`, in URL order.
+fn list(cache: &Cache, now: Timestamp, out: impl Write) -> io::Result<()> {
+    let dash = || "-".to_string();
+    let mut rows: Vec<[String; 7]> = cache
+        .iter()
+        .map(|entry| {
+            let (url, cv) = entry.pair();
+            [
+                url.to_string(),
+                cv.timestamp.strftime("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                cv.etag.clone().unwrap_or_else(dash),
+                cv.last_modified.clone().unwrap_or_else(dash),
+                retry_window_left(cv, now).unwrap_or_else(dash),
+                cv.body
+                    .as_ref()
+                    .map_or_else(dash, |body| body.len().to_string()),
+                notes(cv, now),
+            ]
+        })
+        .collect();
+    rows.sort_unstable();
+    table::write_table(
+        out,
+        [
+            "URL",
+            "FETCHED",
+            "ETAG",
+            "LAST-MODIFIED",
+            "RETRY-AFTER",
+            "BYTES",
+            "NOTES",
+        ],
+        &rows,
+    )
+}
+
+/// The NOTES cell of `cv` in `cache list`.
+fn notes(cv: &CacheValue, now: Timestamp) -> String {
+    let mut notes = Vec::new();
+    if let Some(left) = cv
+        .fresh_until
+        .and_then(|until| Duration::try_from(now.duration_until(until)).ok())
+        .filter(|left| !left.is_zero())
+    {
+        notes.push(format!(
+            "fresh for {}",
+            humantime::format_duration(Duration::from_secs(left.as_secs()))
+        ));
+    }
+    if let Some(feed) = &cv.discovered {
+        notes.push(format!("page advertises {feed}"));
+    }
+    if cv.summary.is_some() {
+        notes.push("article summary".to_string());
+    }
+    if notes.is_empty() {
+        "-".to_string()
+    } else {
+        notes.join("; ")
+    }
+}
+
+/// How long the 429 retry window of `cv` stays open after `now`, if it is
+/// open at all.
+fn retry_window_left(cv: &CacheValue, now: Timestamp) -> Option<String> {
+    if !retry_after_gate_open(cv, now) {
+        return None;
+    }
+    let left = cv
+        .retry_after
+        .and_then(|retry| cv.timestamp.checked_add(retry).ok())
+        .and_then(|deadline| Duration::try_from(now.duration_until(deadline)).ok());
+    // An open gate whose deadline jiff cannot represent stays open for good.
+    Some(left.map_or_else(
+        || "indefinitely".to_string(),
+        |left| humantime::format_duration(Duration::from_secs(left.as_secs())).to_string(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
     use std::{fs::File, thread::sleep, time::Duration as StdDuration};
 
     use jiff::{Span, Timestamp};
-    use tempfile::NamedTempFile;
     use tempfile::TempDir;
     use url::Url;
 
@@ -258,6 +728,32 @@ mod tests {
 
     use super::*;
 
+    /// A cache file in a directory of its own, so the body files stored
+    /// beside it are removed with it.
+    struct TempCache {
+        // Held for its Drop, which removes the directory.
+        _dir: TempDir,
+        path: PathBuf,
+    }
+
+    impl TempCache {
+        fn new() -> Self {
+            let dir = TempDir::new().expect("tempdir");
+            let path = dir.path().join("cache.json");
+            TempCache { _dir: dir, path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl AsRef<Path> for TempCache {
+        fn as_ref(&self) -> &Path {
+            &self.path
+        }
+    }
+
     fn bounded_timestamp(secs: i64) -> Timestamp {
         let secs = secs.clamp(Timestamp::MIN.as_second(), Timestamp::MAX.as_second());
         Timestamp::from_second(secs).expect("failed to clamp timestamp")
@@ -300,6 +796,12 @@ mod tests {
             body: tc.draw(generators::optional(
                 generators::vecs(generators::integers::<u8>()).max_size(64),
             )),
+            // Present or absent, exercising the skip on serialization.
+            discovered: tc
+                .draw(generators::booleans())
+                .then(|| Url::parse("https://example.com/feed.xml").unwrap()),
+            fresh_until: tc.draw(generators::optional(jiff_gs::timestamps())),
+            summary: tc.draw(text()),
         }
     }
 
@@ -333,10 +835,13 @@ mod tests {
             last_modified: None,
             etag: None,
             body: None,
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         cache.insert(url.clone(), cv.clone());
 
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         cache.store(tmp.path()).expect("store succeeds");
 
         let loaded = Cache::load(tmp.path(), u64::MAX, Timestamp::now()).expect("load succeeds");
@@ -351,7 +856,7 @@ mod tests {
         let value = tc.draw(cache_values());
         let cache: Cache = DashMap::new();
         cache.insert(url.clone(), value);
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         cache.store(tmp.path()).expect("store succeeds");
 
         let loaded_large =
@@ -378,7 +883,7 @@ mod tests {
             cache.insert(tc.draw(urls()), tc.draw(cache_values()));
         }
 
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         cache.store(tmp.path()).expect("store succeeds");
 
         let loaded = Cache::load(tmp.path(), u64::MAX, Timestamp::now()).expect("load succeeds");
@@ -421,7 +926,7 @@ mod tests {
             cache.insert(url, value);
         }
 
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         cache.store(tmp.path()).expect("store succeeds");
         let loaded = Cache::load(tmp.path(), max_age.into(), now).expect("load succeeds");
 
@@ -475,7 +980,7 @@ mod tests {
 
         #[rule]
         fn store_reload(&mut self, _tc: TestCase) {
-            let tmp = NamedTempFile::new().expect("temp file");
+            let tmp = TempCache::new();
             self.subject.store(tmp.path()).expect("store succeeds");
             // u64::MAX keeps every entry, so a reload should be a pure round-trip.
             let reloaded =
@@ -518,11 +1023,14 @@ mod tests {
             last_modified: None,
             etag: None,
             body: Some(b"body".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         let cache = Cache::new();
         cache.insert(url.clone(), value);
 
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         cache.store(tmp.path()).expect("initial store");
 
         // A concurrent reader mid-read: holds the shared lock.
@@ -542,6 +1050,9 @@ mod tests {
                     last_modified: None,
                     etag: None,
                     body: None,
+                    discovered: None,
+                    fresh_until: None,
+                    summary: None,
                 },
             );
             cache.store(&path).expect("store after readers release");
@@ -575,11 +1086,14 @@ mod tests {
                 last_modified: None,
                 etag: None,
                 body: None,
+                discovered: None,
+                fresh_until: None,
+                summary: None,
             },
         );
         // The parent "directory" is a file, so every write attempt fails. The
         // cache is an optimization: failure must not propagate to the run.
-        let blocker = NamedTempFile::new().expect("temp file");
+        let blocker = tempfile::NamedTempFile::new().expect("temp file");
         let path = blocker.path().join("sub").join("cache.json");
         super::store_cache(&cache, false, CachePath::Path(&path));
         assert!(!path.exists());
@@ -595,6 +1109,9 @@ mod tests {
             last_modified: None,
             etag: None,
             body: Some(b"body".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         cache.insert(url.clone(), value.clone());
 
@@ -609,9 +1126,384 @@ mod tests {
         assert_eq!(&*loaded.get(&url).expect("key present"), &value);
     }
 
+    fn feed_value(body: &[u8]) -> CacheValue {
+        CacheValue {
+            timestamp: Timestamp::now(),
+            retry_after: None,
+            last_modified: None,
+            etag: Some("\"v1\"".into()),
+            body: Some(body.to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
+        }
+    }
+
+    fn body_files(index: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(body_dir(index))
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn store_keeps_bodies_out_of_the_index_and_dedupes_them() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let cache = Cache::new();
+        // Two URLs serving the same feed share one body file.
+        cache.insert(
+            Url::parse("https://a.example/").unwrap(),
+            feed_value(b"<rss/>"),
+        );
+        cache.insert(
+            Url::parse("https://b.example/").unwrap(),
+            feed_value(b"<rss/>"),
+        );
+        cache.store(&path).unwrap();
+
+        let index = fs::read_to_string(&path).unwrap();
+        assert!(index.starts_with(r#"{"version":2,"#), "{index}");
+        assert!(
+            !index.contains("PHJzcy8+"),
+            "the body leaked into the index"
+        );
+        assert_eq!(body_files(&path), [body_name(b"<rss/>")]);
+    }
+
+    #[test]
+    fn store_leaves_unchanged_files_alone_and_drops_stale_bodies() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let a = Url::parse("https://a.example/").unwrap();
+        let cache = Cache::new();
+        cache.insert(a.clone(), feed_value(b"first"));
+        cache.store(&path).unwrap();
+
+        // Storing the same cache again must write nothing: neither the
+        // index nor a body file is touched.
+        let body = body_dir(&path).join(body_name(b"first"));
+        let index_written = fs::metadata(&path).unwrap().modified().unwrap();
+        let body_written = fs::metadata(&body).unwrap().modified().unwrap();
+        sleep(StdDuration::from_millis(20));
+        cache.store(&path).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().modified().unwrap(),
+            index_written
+        );
+        assert_eq!(
+            fs::metadata(&body).unwrap().modified().unwrap(),
+            body_written
+        );
+
+        // A new body replaces the old one on disk.
+        cache.insert(a.clone(), feed_value(b"second"));
+        cache.store(&path).unwrap();
+        assert_eq!(body_files(&path), [body_name(b"second")]);
+        let loaded = Cache::load(&path, u64::MAX, Timestamp::now()).unwrap();
+        assert_eq!(
+            loaded.get(&a).unwrap().body.as_deref(),
+            Some(&b"second"[..])
+        );
+    }
+
+    #[test]
+    fn caches_differing_only_in_extension_keep_their_own_bodies() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let url = Url::parse("https://a.example/").unwrap();
+        let paths = [
+            tmpdir.path().join("a.json"),
+            tmpdir.path().join("a.idx"),
+            // An index named like a body directory still gets its own.
+            tmpdir.path().join("a.bodies"),
+        ];
+        for path in &paths {
+            let cache = Cache::new();
+            cache.insert(url.clone(), feed_value(path.to_str().unwrap().as_bytes()));
+            cache.store(path).unwrap();
+        }
+
+        for path in &paths {
+            let loaded = Cache::load(path, u64::MAX, Timestamp::now()).unwrap();
+            assert_eq!(
+                loaded.get(&url).unwrap().body.as_deref(),
+                Some(path.to_str().unwrap().as_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn load_drops_only_entries_whose_body_is_lost() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let a = Url::parse("https://a.example/").unwrap();
+        let b = Url::parse("https://b.example/").unwrap();
+        let cache = Cache::new();
+        cache.insert(a.clone(), feed_value(b"kept"));
+        cache.insert(b.clone(), feed_value(b"lost"));
+        cache.store(&path).unwrap();
+        fs::remove_file(body_dir(&path).join(body_name(b"lost"))).unwrap();
+
+        let loaded = Cache::load(&path, u64::MAX, Timestamp::now()).unwrap();
+        assert!(loaded.contains_key(&a));
+        assert!(!loaded.contains_key(&b));
+    }
+
+    #[test]
+    fn inline_caches_migrate_on_the_next_store() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let url = Url::parse("https://a.example/").unwrap();
+        // Layout 1: one pretty-printed map, bodies inline as base64.
+        let old = Cache::new();
+        old.insert(url.clone(), feed_value(b"<rss/>"));
+        fs::write(&path, serde_json::to_string_pretty(&old).unwrap()).unwrap();
+
+        let loaded = Cache::load(&path, u64::MAX, Timestamp::now()).unwrap();
+        assert_eq!(&*loaded.get(&url).unwrap(), &*old.get(&url).unwrap());
+
+        loaded.store(&path).unwrap();
+        assert!(
+            fs::read_to_string(&path)
+                .unwrap()
+                .starts_with(r#"{"version":2,"#)
+        );
+        assert_eq!(body_files(&path), [body_name(b"<rss/>")]);
+        let migrated = Cache::load(&path, u64::MAX, Timestamp::now()).unwrap();
+        assert_eq!(&*migrated.get(&url).unwrap(), &*old.get(&url).unwrap());
+    }
+
+    #[test]
+    fn report_describes_the_cache_file() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+
+        let mut out = Vec::new();
+        super::report(CachePath::Path(&path), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{}: no cache yet\n", path.display())
+        );
+
+        let cache = Cache::new();
+        // An entry far past any --max-cache-age still counts: the report is
+        // about the file, not the next run.
+        cache.insert(
+            Url::parse("https://example.test/").unwrap(),
+            CacheValue {
+                timestamp: Timestamp::UNIX_EPOCH,
+                retry_after: None,
+                last_modified: None,
+                etag: None,
+                body: None,
+                discovered: None,
+                fresh_until: None,
+                summary: None,
+            },
+        );
+        cache.store(&path).unwrap();
+
+        let mut out = Vec::new();
+        super::report(CachePath::Path(&path), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some(path.display().to_string().as_str()));
+        let summary = lines.next().unwrap();
+        assert!(summary.starts_with("1 entries, "), "{summary}");
+    }
+
+    fn run_action(action: CacheAction, path: &Path) -> Result<String> {
+        let mut out = Vec::new();
+        super::command(
+            Some(action),
+            Duration::from_hours(24),
+            CachePath::Path(path),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// A cache with a fresh feed, a week-old feed behind an open 429 window,
+    /// and a discovered homepage.
+    fn stocked_cache(path: &Path, now: Timestamp) {
+        let cache = Cache::new();
+        cache.insert(
+            Url::parse("https://a.example/feed.xml").unwrap(),
+            CacheValue {
+                timestamp: now,
+                retry_after: None,
+                last_modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".into()),
+                etag: Some("\"v1\"".into()),
+                body: Some(b"<rss/>".to_vec()),
+                discovered: None,
+                fresh_until: Some(now + Duration::from_hours(2)),
+                summary: None,
+            },
+        );
+        cache.insert(
+            Url::parse("https://b.example/feed.xml").unwrap(),
+            CacheValue {
+                timestamp: now - Duration::from_hours(7 * 24),
+                retry_after: Some(Span::new().seconds(8 * 24 * 3600)),
+                last_modified: None,
+                etag: None,
+                body: Some(b"<feed/>".to_vec()),
+                discovered: None,
+                fresh_until: None,
+                summary: None,
+            },
+        );
+        cache.insert(
+            Url::parse("https://c.example/").unwrap(),
+            CacheValue {
+                timestamp: now,
+                retry_after: None,
+                last_modified: None,
+                etag: None,
+                body: None,
+                discovered: Some(Url::parse("https://c.example/atom.xml").unwrap()),
+                fresh_until: None,
+                summary: None,
+            },
+        );
+        cache.store(path).unwrap();
+    }
+
+    #[test]
+    fn cache_actions_on_a_missing_file_say_so() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let no_cache = format!("{}: no cache yet\n", path.display());
+        for action in [
+            CacheAction::List,
+            CacheAction::Evict {
+                url: Url::parse("https://a.example/").unwrap(),
+            },
+            CacheAction::ClearGates,
+            CacheAction::Prune { older_than: None },
+        ] {
+            assert_eq!(run_action(action, &path).unwrap(), no_cache);
+        }
+        assert!(!path.exists(), "actions must not create a cache file");
+    }
+
+    #[test]
+    fn list_shows_validators_retry_windows_and_discoveries() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        stocked_cache(&path, Timestamp::now());
+
+        let listing = run_action(CacheAction::List, &path).unwrap();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 4, "{listing}");
+        assert!(lines[0].starts_with("URL "), "{listing}");
+        assert!(lines[1].starts_with("https://a.example/feed.xml "));
+        assert!(lines[1].contains("\"v1\"  Mon, 01 Jan 2024 00:00:00 GMT  -"));
+        assert!(lines[1].contains("  fresh for 1h 59m"), "{listing}");
+        // Opened a week ago for eight days: a day is left.
+        assert!(lines[2].contains("  23h 59m"), "{listing}");
+        assert!(lines[3].ends_with("page advertises https://c.example/atom.xml"));
+    }
+
+    #[test]
+    fn show_prints_the_raw_body() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        stocked_cache(&path, Timestamp::now());
+
+        let show = |url: &str| {
+            run_action(
+                CacheAction::Show {
+                    url: Url::parse(url).unwrap(),
+                },
+                &path,
+            )
+        };
+        assert_eq!(show("https://a.example/feed.xml").unwrap(), "<rss/>");
+        // A discovered page keeps no body of its own.
+        assert!(matches!(
+            show("https://c.example/"),
+            Err(OpenringError::NotCached(_))
+        ));
+        assert!(matches!(
+            show("https://z.example/"),
+            Err(OpenringError::NotCached(_))
+        ));
+    }
+
+    #[test]
+    fn evict_clear_gates_and_prune_edit_the_file() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        let now = Timestamp::now();
+        stocked_cache(&path, now);
+        let reload = || Cache::load(&path, u64::MAX, now).unwrap();
+        let a = Url::parse("https://a.example/feed.xml").unwrap();
+        let b = Url::parse("https://b.example/feed.xml").unwrap();
+
+        assert_eq!(
+            run_action(CacheAction::Evict { url: a.clone() }, &path).unwrap(),
+            "evicted https://a.example/feed.xml\n"
+        );
+        assert_eq!(
+            run_action(CacheAction::Evict { url: a.clone() }, &path).unwrap(),
+            "https://a.example/feed.xml is not cached\n"
+        );
+        assert!(!reload().contains_key(&a));
+
+        assert_eq!(
+            run_action(CacheAction::ClearGates, &path).unwrap(),
+            "cleared 1 429 retry windows\n"
+        );
+        let cache = reload();
+        let cv = cache.get(&b).unwrap();
+        assert!(cv.retry_after.is_none());
+        assert_eq!(cv.body.as_deref(), Some(&b"<feed/>"[..]), "the body stays");
+        drop(cv);
+
+        assert_eq!(
+            run_action(
+                CacheAction::Prune {
+                    older_than: Some(Duration::from_hours(3 * 24))
+                },
+                &path
+            )
+            .unwrap(),
+            "pruned 1 entries fetched more than 3days ago\n"
+        );
+        assert!(!reload().contains_key(&b));
+        assert_eq!(reload().len(), 1);
+    }
+
+    #[test]
+    fn edits_that_change_nothing_write_nothing() {
+        let tmpdir = TempDir::new().expect("tempdir");
+        let path = tmpdir.path().join("cache.json");
+        stocked_cache(&path, Timestamp::now());
+        run_action(CacheAction::ClearGates, &path).unwrap();
+        // A write would sweep this unreferenced body away.
+        let stray = body_dir(&path).join("stray");
+        fs::write(&stray, "x").unwrap();
+
+        for action in [
+            CacheAction::Evict {
+                url: Url::parse("https://z.example/").unwrap(),
+            },
+            CacheAction::ClearGates,
+            CacheAction::Prune {
+                older_than: Some(Duration::from_hours(365 * 24)),
+            },
+        ] {
+            run_action(action, &path).unwrap();
+        }
+        assert!(stray.exists());
+    }
+
     #[test]
     fn load_cache_discards_old_format_plain_text_bodies() {
-        let tmp = NamedTempFile::new().expect("temp file");
+        let tmp = TempCache::new();
         // A cache written by versions that stored the body as plain text. The
         // body is not valid base64, so the whole file is discarded and the
         // run continues cacheless instead of erroring.
@@ -628,7 +1520,7 @@ mod tests {
 
     #[test]
     fn load_cache_returns_none_when_cache_disabled() {
-        let tmp_cache_path = NamedTempFile::new().expect("temp file");
+        let tmp_cache_path = TempCache::new();
         let mut args = Args {
             no_cache: true,
             ..Default::default()
@@ -652,7 +1544,7 @@ mod tests {
 
     #[test]
     fn load_cache_discards_too_old_file_and_returns_none() {
-        let tmp_cache_path = NamedTempFile::new().expect("temp file");
+        let tmp_cache_path = TempCache::new();
         File::create(&tmp_cache_path).expect("create cache file");
 
         // Ensure the file's mtime is at least in the past relative to the check
@@ -671,7 +1563,7 @@ mod tests {
 
     #[test]
     fn load_cache_uses_recent_file_and_loads_entries() {
-        let tmp_cache_path = NamedTempFile::new().expect("temp file");
+        let tmp_cache_path = TempCache::new();
 
         // Prepare a real cache JSON under the expected filename
         let url = Url::parse("https://example.test/").unwrap();
@@ -681,6 +1573,9 @@ mod tests {
             last_modified: Some("Mon, 01 Jan 2000 00:00:00 GMT".into()),
             etag: Some("etag".into()),
             body: Some(b"body".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         let cache = Cache::new();
         cache.insert(url.clone(), value.clone());
@@ -702,7 +1597,7 @@ mod tests {
 
     #[test]
     fn cache_round_trip_prunes_entries_that_are_old() {
-        let tmp_cache_path = NamedTempFile::new().expect("temp file");
+        let tmp_cache_path = TempCache::new();
 
         // Prepare a real cache JSON under the expected filename
         let cache = Cache::new();
@@ -713,6 +1608,9 @@ mod tests {
             last_modified: Some("Mon, 01 Jan 2000 00:00:00 GMT".into()),
             etag: Some("etag".into()),
             body: Some(b"body".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         cache.insert(valid_url.clone(), valid_value.clone());
 
@@ -724,6 +1622,9 @@ mod tests {
             last_modified: Some("Mon, 01 Jan 2000 00:00:00 GMT".into()),
             etag: Some("etag".into()),
             body: Some(b"body".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
         };
         cache.insert(expired_url.clone(), expired_value.clone());
         cache.store(&tmp_cache_path).expect("store");
@@ -0,0 +1,869 @@
+//! Project configuration from an `openring.toml` file, layered beneath the
+//! command line.
+//!
+//! Every option `Args` takes can be set in the file, and each feed gets a
+//! `[[feed]]` table. A flag given on the command line always wins over the
+//! file, and relative paths in the file resolve against the file's own
+//! directory, so a site build can run openring from anywhere.
+
+use std::{
+    collections::HashMap,
+    fs,
+    num::NonZeroUsize,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{ArgMatches, parser::ValueSource};
+use miette::NamedSource;
+use serde::Deserialize;
+use toml::{Spanned, value::Datetime};
+use tracing::info;
+use url::Url;
+
+use crate::{
+    FeedAttr, FeedAttrs,
+    args::Args,
+    error::{ConfigError, FeedAttributeError, FeedUrlError, FeedWeightError, Result},
+    feedfetcher::{ProxySetting, parse_byte_size},
+    record_spanned_feed,
+    target::Target,
+    window::DateBound,
+};
+
+/// The file looked for in the working directory when `--config` is absent.
+pub const DEFAULT_CONFIG_FILE: &str = "openring.toml";
+
+/// The file as written, before validation. Unknown keys are rejected so a
+/// typo fails loudly instead of silently doing nothing.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    num_articles: Option<usize>,
+    per_source: Option<usize>,
+    url_file: Option<PathBuf>,
+    opml: Option<PathBuf>,
+    export_opml: Option<PathBuf>,
+    template_file: Option<PathBuf>,
+    before: Option<Spanned<DateValue>>,
+    after: Option<Spanned<DateValue>>,
+    max_age: Option<Spanned<String>>,
+    no_cache: Option<Spanned<bool>>,
+    offline: Option<Spanned<bool>>,
+    revalidate: Option<Spanned<bool>>,
+    cache_file: Option<PathBuf>,
+    max_cache_age: Option<Spanned<String>>,
+    stale_if_error: Option<Spanned<String>>,
+    min_interval: Option<Spanned<String>>,
+    retries: Option<u32>,
+    time_budget: Option<Spanned<String>>,
+    max_per_host: Option<usize>,
+    host_delay: Option<Spanned<String>>,
+    max_concurrent: Option<usize>,
+    connect_timeout: Option<Spanned<String>>,
+    read_timeout: Option<Spanned<String>>,
+    request_timeout: Option<Spanned<String>>,
+    user_agent_suffix: Option<String>,
+    contact: Option<String>,
+    max_feed_size: Option<Spanned<String>>,
+    max_page_size: Option<Spanned<String>>,
+    proxy: Option<Spanned<String>>,
+    ca_file: Option<PathBuf>,
+    seed: Option<u64>,
+    tag: Option<Vec<String>>,
+    #[serde(default)]
+    feed: Vec<RawFeed>,
+    #[serde(default)]
+    target: Vec<RawTarget>,
+}
+
+/// One `[[target]]` table.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawTarget {
+    template_file: PathBuf,
+    /// Absent means stdout.
+    output: Option<PathBuf>,
+    num_articles: Option<usize>,
+    per_source: Option<usize>,
+}
+
+/// One `[[feed]]` table.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawFeed {
+    url: Spanned<String>,
+    weight: Option<Spanned<i64>>,
+    title: Option<Spanned<String>>,
+    homepage: Option<Spanned<String>>,
+    per_source: Option<Spanned<usize>>,
+    min_interval: Option<Spanned<String>>,
+    proxy: Option<Spanned<String>>,
+    tags: Option<Spanned<Vec<String>>>,
+    note: Option<Spanned<String>>,
+}
+
+/// A date or timestamp written either as a TOML literal
+/// (`before = 2024-01-01`, `after = 2024-01-01T00:00:00Z`) or as a string
+/// (`before = "2024-01-01"`).
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum DateValue {
+    Toml(Datetime),
+    Text(String),
+}
+
+impl DateValue {
+    fn text(&self) -> String {
+        match self {
+            DateValue::Toml(dt) => dt.to_string(),
+            DateValue::Text(s) => s.clone(),
+        }
+    }
+}
+
+/// A validated config file, with paths already resolved against its
+/// directory.
+#[derive(Debug, Default)]
+pub(crate) struct Config {
+    num_articles: Option<usize>,
+    per_source: Option<usize>,
+    url_file: Option<PathBuf>,
+    opml: Option<PathBuf>,
+    export_opml: Option<PathBuf>,
+    template_file: Option<PathBuf>,
+    before: Option<DateBound>,
+    after: Option<DateBound>,
+    max_age: Option<Duration>,
+    no_cache: Option<Spanned<bool>>,
+    offline: Option<Spanned<bool>>,
+    revalidate: Option<Spanned<bool>>,
+    cache_file: Option<PathBuf>,
+    max_cache_age: Option<Duration>,
+    stale_if_error: Option<Duration>,
+    min_interval: Option<Duration>,
+    retries: Option<u32>,
+    time_budget: Option<Duration>,
+    max_per_host: Option<usize>,
+    host_delay: Option<Duration>,
+    max_concurrent: Option<usize>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    user_agent_suffix: Option<String>,
+    contact: Option<String>,
+    max_feed_size: Option<u64>,
+    max_page_size: Option<u64>,
+    proxy: Option<ProxySetting>,
+    ca_file: Option<PathBuf>,
+    seed: Option<u64>,
+    tag: Option<Vec<String>>,
+    /// Every `[[feed]]` table, merged per [`FeedAttrs::merge`].
+    pub(crate) feeds: HashMap<Url, FeedAttrs>,
+    targets: Vec<Target>,
+    /// The file itself, for diagnostics about how it combines with the
+    /// command line.
+    src: Option<NamedSource<String>>,
+}
+
+impl Config {
+    /// Read and validate the config file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not valid TOML, holds
+    /// an unknown key or a value of the wrong type, or a value fails to
+    /// parse. Each diagnostic points at the offending key or value.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let file_src = fs::read_to_string(path)?;
+        let named = || NamedSource::new(path.to_string_lossy(), file_src.clone());
+        let config_error = |span: Range<usize>, help: String| ConfigError {
+            src: named(),
+            span: span.into(),
+            help,
+        };
+
+        let raw: RawConfig = toml::from_str(&file_src)
+            .map_err(|e| config_error(e.span().unwrap_or(0..0), e.message().to_string()))?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resolve = |p: PathBuf| dir.join(p);
+
+        let date_bound = |spanned: Spanned<DateValue>| {
+            let span = spanned.span();
+            spanned
+                .into_inner()
+                .text()
+                .parse::<DateBound>()
+                .map_err(|e| config_error(span, e))
+        };
+        let duration = |spanned: Spanned<String>| {
+            humantime::parse_duration(spanned.get_ref()).map_err(|e| {
+                config_error(
+                    spanned.span(),
+                    format!("expected a duration like \"30d\" or \"12h\" ({e})"),
+                )
+            })
+        };
+        let byte_size = |spanned: Spanned<String>| {
+            parse_byte_size(spanned.get_ref()).map_err(|e| config_error(spanned.span(), e))
+        };
+        let proxy = |spanned: Spanned<String>| {
+            spanned
+                .get_ref()
+                .parse::<ProxySetting>()
+                .map_err(|e| config_error(spanned.span(), e))
+        };
+
+        Ok(Config {
+            num_articles: raw.num_articles,
+            per_source: raw.per_source,
+            // A remote list is a URL, not a path relative to this file.
+            url_file: raw.url_file.map(|p| {
+                if crate::remote_list_url(&p).is_some() {
+                    p
+                } else {
+                    resolve(p)
+                }
+            }),
+            opml: raw.opml.map(resolve),
+            export_opml: raw.export_opml.map(resolve),
+            template_file: raw.template_file.map(resolve),
+            before: raw.before.map(date_bound).transpose()?,
+            after: raw.after.map(date_bound).transpose()?,
+            max_age: raw.max_age.map(duration).transpose()?,
+            no_cache: raw.no_cache,
+            offline: raw.offline,
+            revalidate: raw.revalidate,
+            cache_file: raw.cache_file.map(resolve),
+            max_cache_age: raw.max_cache_age.map(duration).transpose()?,
+            stale_if_error: raw.stale_if_error.map(duration).transpose()?,
+            min_interval: raw.min_interval.map(duration).transpose()?,
+            retries: raw.retries,
+            time_budget: raw.time_budget.map(duration).transpose()?,
+            max_per_host: raw.max_per_host,
+            host_delay: raw.host_delay.map(duration).transpose()?,
+            max_concurrent: raw.max_concurrent,
+            connect_timeout: raw.connect_timeout.map(duration).transpose()?,
+            read_timeout: raw.read_timeout.map(duration).transpose()?,
+            request_timeout: raw.request_timeout.map(duration).transpose()?,
+            user_agent_suffix: raw.user_agent_suffix,
+            contact: raw.contact,
+            max_feed_size: raw.max_feed_size.map(byte_size).transpose()?,
+            max_page_size: raw.max_page_size.map(byte_size).transpose()?,
+            proxy: raw.proxy.map(proxy).transpose()?,
+            ca_file: raw.ca_file.map(resolve),
+            seed: raw.seed,
+            tag: raw.tag,
+            feeds: load_feeds(raw.feed, &named)?,
+            targets: raw
+                .target
+                .into_iter()
+                .map(|t| Target {
+                    template_file: resolve(t.template_file),
+                    output: t.output.map(resolve),
+                    num_articles: t.num_articles,
+                    per_source: t.per_source,
+                })
+                .collect(),
+            src: Some(named()),
+        })
+    }
+
+    /// Fill every option of `args` that `matches` says was not given on the
+    /// command line (or through the environment) from this file, and hand
+    /// the `[[feed]]` tables over to be merged with the other feed sources.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file turns on `offline` together with
+    /// `no_cache` or `revalidate`, either itself or on top of the command
+    /// line, which clap cannot catch.
+    fn layer_under(self, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+        let from_file = |id: &str| {
+            !matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        // Options with a default: a value in the file replaces it.
+        macro_rules! replace {
+            ($($key:ident),* $(,)?) => {$(
+                if from_file(stringify!($key))
+                    && let Some(value) = self.$key
+                {
+                    args.$key = value;
+                }
+            )*};
+        }
+        // Optional flags: a value in the file fills a flag left unset.
+        macro_rules! fill {
+            ($($key:ident),* $(,)?) => {$(
+                if from_file(stringify!($key)) && self.$key.is_some() {
+                    args.$key = self.$key;
+                }
+            )*};
+        }
+        // Switches: a negating flag on the command line keeps the file's
+        // value out too. Returns where the file turned the switch on.
+        let switch = |value: Option<Spanned<bool>>, id, negation: Option<&str>, arg: &mut bool| {
+            let value = value.filter(|_| from_file(id) && negation.is_none_or(from_file))?;
+            *arg = *value.get_ref();
+            arg.then(|| value.span())
+        };
+        let no_cache = switch(
+            self.no_cache,
+            "no_cache",
+            Some("use_cache"),
+            &mut args.no_cache,
+        );
+        let offline = switch(self.offline, "offline", Some("online"), &mut args.offline);
+        let revalidate = switch(self.revalidate, "revalidate", None, &mut args.revalidate);
+        replace!(
+            num_articles,
+            per_source,
+            max_cache_age,
+            stale_if_error,
+            min_interval,
+            retries,
+            time_budget,
+            max_per_host,
+            host_delay,
+            max_concurrent,
+            connect_timeout,
+            read_timeout,
+            request_timeout,
+            max_feed_size,
+            max_page_size,
+            tag,
+        );
+        fill!(
+            user_agent_suffix,
+            contact,
+            proxy,
+            ca_file,
+            url_file,
+            opml,
+            export_opml,
+            cache_file,
+            template_file,
+            before,
+            after,
+            max_age,
+            seed,
+        );
+        args.config_feeds = self.feeds;
+        args.config_targets = self.targets;
+
+        if !args.offline {
+            return Ok(());
+        }
+        let conflicts = [
+            (
+                args.no_cache,
+                no_cache,
+                "an offline run reads only the cache, which `no_cache` turns off; drop one of \
+                 the two, or pass --online or --use-cache",
+            ),
+            (
+                args.revalidate,
+                revalidate,
+                "an offline run sends no requests, which `revalidate` asks for; drop one of the \
+                 two, or pass --online",
+            ),
+        ];
+        for (on, span, help) in conflicts {
+            // Clap rejects the pair when both come from the command line,
+            // so one of the two spans is set.
+            if let (true, Some(span), Some(src)) = (on, span.or(offline.clone()), &self.src) {
+                return Err(ConfigError {
+                    src: src.clone(),
+                    span: span.into(),
+                    help: help.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validate the `[[feed]]` tables and merge them per [`FeedAttrs::merge`],
+/// with `named` producing the config file as diagnostic source.
+fn load_feeds(
+    tables: Vec<RawFeed>,
+    named: &impl Fn() -> NamedSource<String>,
+) -> Result<HashMap<Url, FeedAttrs>> {
+    let mut feeds: HashMap<Url, FeedAttrs> = HashMap::new();
+    for table in tables {
+        let url = Url::parse(table.url.get_ref().trim()).map_err(|e| FeedUrlError {
+            src: named(),
+            span: table.url.span().into(),
+            help: e.to_string(),
+        })?;
+        let attribute_error = |span: Range<usize>, help: String| FeedAttributeError {
+            src: named(),
+            span: span.into(),
+            help,
+        };
+        let mut attrs = Vec::new();
+        if let Some(w) = table.weight {
+            let weight = usize::try_from(*w.get_ref())
+                .ok()
+                .and_then(NonZeroUsize::new)
+                .ok_or_else(|| FeedWeightError {
+                    src: named(),
+                    span: w.span().into(),
+                    help: "the weight must be a positive integer; omit it for the default unweighted behavior".to_string(),
+                })?;
+            attrs.push((FeedAttr::Weight(weight), w.span()));
+        }
+        if let Some(title) = table.title {
+            let attr = FeedAttr::title(title.get_ref())
+                .map_err(|help| attribute_error(title.span(), help))?;
+            attrs.push((attr, title.span()));
+        }
+        if let Some(homepage) = table.homepage {
+            let attr = FeedAttr::homepage(homepage.get_ref())
+                .map_err(|help| attribute_error(homepage.span(), help))?;
+            attrs.push((attr, homepage.span()));
+        }
+        if let Some(n) = table.per_source {
+            attrs.push((FeedAttr::PerSource(*n.get_ref()), n.span()));
+        }
+        if let Some(interval) = table.min_interval {
+            let attr = FeedAttr::min_interval(interval.get_ref())
+                .map_err(|help| attribute_error(interval.span(), help))?;
+            attrs.push((attr, interval.span()));
+        }
+        if let Some(proxy) = table.proxy {
+            let attr = FeedAttr::proxy(proxy.get_ref())
+                .map_err(|help| attribute_error(proxy.span(), help))?;
+            attrs.push((attr, proxy.span()));
+        }
+        if let Some(tags) = table.tags {
+            let attr = FeedAttr::tags(tags.get_ref().iter().map(String::as_str))
+                .map_err(|help| attribute_error(tags.span(), help))?;
+            attrs.push((attr, tags.span()));
+        }
+        if let Some(note) = table.note {
+            let span = note.span();
+            attrs.push((FeedAttr::Note(note.into_inner()), span));
+        }
+        record_spanned_feed(&mut feeds, url, attrs, named, 0)?;
+    }
+    Ok(feeds)
+}
+
+/// Find the config file to use: `--config` if given, otherwise
+/// [`DEFAULT_CONFIG_FILE`] in the working directory if it exists.
+fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+    default.is_file().then_some(default)
+}
+
+/// Layer the config file, if there is one, beneath the options parsed from
+/// the command line. `matches` must be the matches `args` was built from, so
+/// flags given explicitly keep their values.
+///
+/// # Errors
+///
+/// Returns an error if `--config` names a file that cannot be read, or the
+/// config file fails to validate (see [`Config::load`]).
+pub fn apply(args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let Some(path) = locate(args.config.as_deref()) else {
+        return Ok(());
+    };
+    let config = Config::load(&path)?;
+    info!(path = %path.display(), "using config file");
+    config.layer_under(args, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, num::NonZeroUsize, time::Duration};
+
+    use clap::{CommandFactory, FromArgMatches};
+    use url::Url;
+
+    use super::{Config, apply};
+    use crate::{
+        args::Args, error::OpenringError, feedfetcher::ProxySetting, target::Target,
+        window::DateBound,
+    };
+
+    fn config_file(body: &str) -> tempfile::NamedTempFile {
+        let mut tmp = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        write!(tmp, "{body}").unwrap();
+        tmp
+    }
+
+    // Parse argv the way main does, then layer the given config beneath it.
+    fn args_with_config(cli: &[&str], config: &tempfile::NamedTempFile) -> Args {
+        let mut cli = cli.to_vec();
+        cli.extend(["--config", config.path().to_str().unwrap()]);
+        let matches = Args::command().try_get_matches_from(cli).unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        apply(&mut args, &matches).unwrap();
+        args
+    }
+
+    #[test]
+    fn config_fills_options_the_command_line_left_out() {
+        let config = config_file(
+            r#"
+            num_articles = 9
+            per_source = 2
+            template_file = "in.html"
+            before = 2024-01-31
+            max_cache_age = "12h"
+            stale_if_error = "2d"
+            revalidate = true
+            min_interval = "6h"
+            retries = 5
+            time_budget = "90s"
+            max_per_host = 1
+            host_delay = "2s"
+            max_concurrent = 8
+            connect_timeout = "5s"
+            read_timeout = "20s"
+            request_timeout = "1m"
+            user_agent_suffix = "(+https://example.com/blogroll)"
+            contact = "webmaster@example.com"
+            max_feed_size = "16MiB"
+            max_page_size = "512 KiB"
+            proxy = "socks5h://127.0.0.1:9050"
+            ca_file = "certs/ca.pem"
+            seed = 7
+            "#,
+        );
+
+        let args = args_with_config(&["openring"], &config);
+
+        assert_eq!(args.num_articles, 9);
+        assert_eq!(args.per_source, 2);
+        assert_eq!(args.seed, Some(7));
+        assert_eq!(args.max_cache_age, Duration::from_hours(12));
+        assert_eq!(args.stale_if_error, Duration::from_hours(48));
+        assert!(args.revalidate);
+        assert_eq!(args.min_interval, Duration::from_hours(6));
+        assert_eq!(args.retries, 5);
+        assert_eq!(args.time_budget, Duration::from_secs(90));
+        assert_eq!(args.max_per_host, 1);
+        assert_eq!(args.host_delay, Duration::from_secs(2));
+        assert_eq!(args.max_concurrent, 8);
+        assert_eq!(args.connect_timeout, Duration::from_secs(5));
+        assert_eq!(args.read_timeout, Duration::from_secs(20));
+        assert_eq!(args.request_timeout, Duration::from_mins(1));
+        assert_eq!(
+            args.user_agent_suffix.as_deref(),
+            Some("(+https://example.com/blogroll)")
+        );
+        assert_eq!(args.contact.as_deref(), Some("webmaster@example.com"));
+        assert_eq!(args.max_feed_size, 16 << 20);
+        assert_eq!(args.max_page_size, 512 << 10);
+        assert_eq!(
+            args.proxy.map(|proxy| proxy.to_string()).as_deref(),
+            Some("socks5h://127.0.0.1:9050")
+        );
+        assert_eq!(
+            args.ca_file,
+            Some(config.path().parent().unwrap().join("certs/ca.pem"))
+        );
+        assert_eq!(
+            args.before,
+            Some(DateBound::Date(jiff::civil::date(2024, 1, 31)))
+        );
+        // Paths resolve against the config file's directory, not the
+        // working directory.
+        assert_eq!(
+            args.template_file,
+            Some(config.path().parent().unwrap().join("in.html"))
+        );
+    }
+
+    #[test]
+    fn command_line_flags_override_the_config_file() {
+        let config = config_file(
+            r#"
+            num_articles = 9
+            no_cache = true
+            before = "2024-01-31"
+            "#,
+        );
+
+        let args = args_with_config(&["openring", "-n", "4", "--before", "2020-05-05"], &config);
+
+        assert_eq!(args.num_articles, 4);
+        assert_eq!(
+            args.before,
+            Some(DateBound::Date(jiff::civil::date(2020, 5, 5)))
+        );
+        // Untouched on the command line, so the file's value applies.
+        assert!(args.no_cache);
+    }
+
+    #[test]
+    fn negating_flags_turn_off_switches_the_file_turns_on() {
+        let config = config_file("offline = true\nno_cache = false\n");
+        assert!(args_with_config(&["openring"], &config).offline);
+        assert!(!args_with_config(&["openring", "--online"], &config).offline);
+        // The last of the two flags wins.
+        assert!(!args_with_config(&["openring", "--offline", "--online"], &config).offline);
+        assert!(args_with_config(&["openring", "--online", "--offline"], &config).offline);
+
+        let config = config_file("no_cache = true\n");
+        assert!(args_with_config(&["openring"], &config).no_cache);
+        assert!(!args_with_config(&["openring", "--use-cache"], &config).no_cache);
+    }
+
+    #[test]
+    fn offline_cannot_combine_with_no_cache_or_revalidate() {
+        let apply_to = |cli: &[&str], body: &str| {
+            let config = config_file(body);
+            let mut cli = cli.to_vec();
+            cli.extend(["--config", config.path().to_str().unwrap()]);
+            let matches = Args::command().try_get_matches_from(cli).unwrap();
+            let mut args = Args::from_arg_matches(&matches).unwrap();
+            apply(&mut args, &matches).map(|()| args)
+        };
+        // The diagnostic points at the key in the file that clashes.
+        for (cli, body, key) in [
+            (
+                &["openring"][..],
+                "offline = true\nno_cache = true\n",
+                "no_cache",
+            ),
+            (
+                &["openring"],
+                "offline = true\nrevalidate = true\n",
+                "revalidate",
+            ),
+            (&["openring", "--no-cache"], "offline = true\n", "offline"),
+            (&["openring", "--revalidate"], "offline = true\n", "offline"),
+            (&["openring", "--offline"], "no_cache = true\n", "no_cache"),
+        ] {
+            let err = apply_to(cli, body).unwrap_err();
+            let OpenringError::ConfigError(e) = err else {
+                panic!("expected ConfigError, got {err:?}");
+            };
+            assert_eq!(
+                e.span.offset(),
+                body.find(key).unwrap() + key.len() + 3,
+                "{body}"
+            );
+        }
+
+        let args = apply_to(
+            &["openring", "--online"],
+            "offline = true\nno_cache = true\n",
+        );
+        assert!(args.is_ok_and(|args| args.no_cache && !args.offline));
+        let args = apply_to(
+            &["openring", "--use-cache"],
+            "offline = true\nno_cache = true\n",
+        );
+        assert!(args.is_ok_and(|args| args.offline && !args.no_cache));
+    }
+
+    #[test]
+    fn cache_file_resolves_against_the_config_file() {
+        let config = config_file("cache_file = \".cache/openring.json\"\n");
+
+        let args = args_with_config(&["openring"], &config);
+        assert_eq!(
+            args.cache_file,
+            Some(config.path().parent().unwrap().join(".cache/openring.json"))
+        );
+
+        let args = args_with_config(&["openring", "--cache-file", "ci.json"], &config);
+        assert_eq!(args.cache_file, Some("ci.json".into()));
+    }
+
+    #[test]
+    fn flags_after_a_subcommand_override_the_config_file_too() {
+        let config = config_file("num_articles = 9\nper_source = 3\n");
+
+        // args_with_config appends --config, so it also lands after the
+        // subcommand.
+        let args = args_with_config(&["openring", "feeds", "-n", "4"], &config);
+
+        assert_eq!(args.command, Some(crate::args::Command::Feeds));
+        assert_eq!(args.num_articles, 4);
+        assert_eq!(args.per_source, 3);
+    }
+
+    #[test]
+    fn config_takes_timestamp_bounds_and_a_max_age() {
+        let config = config_file(
+            r#"
+            after = 2024-01-01T00:00:00+01:00
+            before = "2024-02-01T00:00:00Z"
+            max_age = "180d"
+            "#,
+        );
+
+        let args = args_with_config(&["openring"], &config);
+
+        assert_eq!(
+            args.after,
+            Some(DateBound::Timestamp(
+                "2023-12-31T23:00:00Z".parse().unwrap()
+            ))
+        );
+        assert_eq!(
+            args.before,
+            Some(DateBound::Timestamp(
+                "2024-02-01T00:00:00Z".parse().unwrap()
+            ))
+        );
+        assert_eq!(args.max_age, Some(Duration::from_hours(180 * 24)));
+    }
+
+    #[test]
+    fn bounds_without_an_offset_point_at_the_value() {
+        let body = "after = \"2024-01-01T00:00\"\n";
+        let config = config_file(body);
+
+        let err = Config::load(config.path()).unwrap_err();
+        let OpenringError::ConfigError(e) = err else {
+            panic!("expected ConfigError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find('"').unwrap());
+        assert!(e.help.contains("offset"), "help: {}", e.help);
+    }
+
+    #[test]
+    fn config_feed_tables_carry_urls_and_attributes() {
+        let config = config_file(
+            r#"
+            [[feed]]
+            url = "https://a.example/feed.xml"
+            weight = 7
+            title = "Alice's blog"
+            min_interval = "12h"
+            proxy = "none"
+            tags = ["rust", "systems"]
+
+            [[feed]]
+            url = "https://b.example/feed.xml"
+            "#,
+        );
+
+        let args = args_with_config(&["openring"], &config);
+
+        assert_eq!(args.config_feeds.len(), 2);
+        let a = &args.config_feeds[&Url::parse("https://a.example/feed.xml").unwrap()];
+        assert_eq!(a.weight, NonZeroUsize::new(7));
+        assert_eq!(a.title.as_deref(), Some("Alice's blog"));
+        assert_eq!(a.min_interval, Some(Duration::from_hours(12)));
+        assert_eq!(a.proxy, Some(ProxySetting::Direct));
+        assert_eq!(a.tags, ["rust", "systems"]);
+    }
+
+    #[test]
+    fn config_target_tables_resolve_paths_against_the_file() {
+        let config = config_file(
+            r#"
+            [[target]]
+            template_file = "sidebar.html"
+            output = "out/sidebar.html"
+            num_articles = 5
+            "#,
+        );
+
+        let args = args_with_config(&["openring"], &config);
+
+        let dir = config.path().parent().unwrap();
+        assert_eq!(
+            args.config_targets,
+            [Target {
+                template_file: dir.join("sidebar.html"),
+                output: Some(dir.join("out/sidebar.html")),
+                num_articles: Some(5),
+                per_source: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_point_at_the_key() {
+        let body = "num_articles = 3\nnum_artcles = 4\n";
+        let config = config_file(body);
+
+        let err = Config::load(config.path()).unwrap_err();
+        let OpenringError::ConfigError(e) = err else {
+            panic!("expected ConfigError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find("num_artcles").unwrap());
+    }
+
+    #[test]
+    fn wrong_types_point_at_the_value() {
+        let body = "per_source = \"two\"\n";
+        let config = config_file(body);
+
+        let err = Config::load(config.path()).unwrap_err();
+        let OpenringError::ConfigError(e) = err else {
+            panic!("expected ConfigError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find("\"two\"").unwrap());
+    }
+
+    #[test]
+    fn invalid_values_point_at_the_value() {
+        let body = "max_cache_age = \"soon\"\n";
+        let config = config_file(body);
+
+        let err = Config::load(config.path()).unwrap_err();
+        let OpenringError::ConfigError(e) = err else {
+            panic!("expected ConfigError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find("\"soon\"").unwrap());
+        assert_eq!(e.span.len(), "\"soon\"".len());
+    }
+
+    #[test]
+    fn bad_feed_tables_get_feed_diagnostics() {
+        let body = "[[feed]]\nurl = \"not a url\"\n";
+        let err = Config::load(config_file(body).path()).unwrap_err();
+        let OpenringError::FeedUrlError(e) = err else {
+            panic!("expected FeedUrlError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find("\"not a url\"").unwrap());
+
+        let body = "[[feed]]\nurl = \"https://a.example/\"\nweight = 0\n";
+        let err = Config::load(config_file(body).path()).unwrap_err();
+        let OpenringError::FeedWeightError(e) = err else {
+            panic!("expected FeedWeightError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find('0').unwrap());
+    }
+
+    #[test]
+    fn conflicting_feed_weights_point_at_the_second_weight() {
+        let body = "[[feed]]\nurl = \"https://a.example/\"\nweight = 3\n\n[[feed]]\nurl = \"https://a.example/\"\nweight = 7\n";
+        let err = Config::load(config_file(body).path()).unwrap_err();
+        let OpenringError::FeedWeightError(e) = err else {
+            panic!("expected FeedWeightError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), body.find('7').unwrap());
+        assert!(e.help.contains('3'), "help: {}", e.help);
+    }
+
+    #[test]
+    fn missing_explicit_config_is_an_error() {
+        let matches = Args::command()
+            .try_get_matches_from(["openring", "--config", "/nonexistent/openring.toml"])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        assert!(matches!(
+            apply(&mut args, &matches),
+            Err(OpenringError::IoError(_))
+        ));
+    }
+}
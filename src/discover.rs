@@ -0,0 +1,118 @@
+//! Find the feed a web page advertises, so a feed list can name a site's
+//! homepage instead of its feed.
+
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Media types of the feed formats the feed parser reads, as they appear in
+/// the `type` of an advertising `<link>`.
+const FEED_TYPES: [&str; 3] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+/// The feed advertised by the HTML page at `page_url`: the first
+/// `<link rel="alternate">` whose `type` is a feed format, resolved against
+/// the page's `<base href>` or else `page_url`.
+///
+/// Pages often advertise several feeds (posts, comments, per-category);
+/// the first is by convention the site's main feed. `None` means the page
+/// advertises no feed, or is not HTML at all.
+pub(crate) fn discover_feed(html: &str, page_url: &Url) -> Option<Url> {
+    let doc = Html::parse_document(html);
+    let base_selector = Selector::parse("base[href]").expect("selector literal is valid");
+    let base = doc
+        .select(&base_selector)
+        .find_map(|el| el.value().attr("href"))
+        .and_then(|href| page_url.join(href).ok())
+        .unwrap_or_else(|| page_url.clone());
+
+    let link_selector =
+        Selector::parse("link[rel][type][href]").expect("selector literal is valid");
+    doc.select(&link_selector)
+        .map(|el| el.value())
+        .filter(|link| {
+            link.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|token| token.eq_ignore_ascii_case("alternate"))
+            })
+        })
+        .filter(|link| link.attr("type").is_some_and(is_feed_type))
+        .find_map(|link| base.join(link.attr("href")?.trim()).ok())
+}
+
+/// Whether a `type` attribute names a feed format, ignoring case and any
+/// parameters like `charset`.
+fn is_feed_type(value: &str) -> bool {
+    let mime = value.split(';').next().unwrap_or("").trim();
+    FEED_TYPES
+        .iter()
+        .any(|feed_type| mime.eq_ignore_ascii_case(feed_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_url() -> Url {
+        Url::parse("https://blog.example/about/").unwrap()
+    }
+
+    #[test]
+    fn discovers_each_feed_format() {
+        for feed_type in FEED_TYPES {
+            let html = format!(
+                r#"<html><head><link rel="alternate" type="{feed_type}" href="/feed"></head></html>"#
+            );
+            assert_eq!(
+                discover_feed(&html, &page_url()).map(String::from),
+                Some("https://blog.example/feed".to_string()),
+                "{feed_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn picks_the_first_advertised_feed() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" type="text/css" href="/style.css">
+            <link rel="alternate" type="text/html" hreflang="de" href="/de/">
+            <link rel="alternate" type="application/atom+xml" href="posts.atom" title="Posts">
+            <link rel="alternate" type="application/rss+xml" href="comments.rss" title="Comments">
+            </head></html>"#;
+        assert_eq!(
+            discover_feed(html, &page_url()).map(String::from),
+            Some("https://blog.example/about/posts.atom".to_string())
+        );
+    }
+
+    #[test]
+    fn matches_rel_tokens_and_types_loosely() {
+        let html = r#"<link rel="Alternate feed" type="Application/RSS+XML; charset=utf-8" href=" https://feeds.example/blog ">"#;
+        assert_eq!(
+            discover_feed(html, &page_url()).map(String::from),
+            Some("https://feeds.example/blog".to_string())
+        );
+    }
+
+    #[test]
+    fn resolves_against_the_base_element() {
+        let html = r#"<html><head>
+            <base href="https://cdn.example/site/">
+            <link rel="alternate" type="application/atom+xml" href="atom.xml">
+            </head></html>"#;
+        assert_eq!(
+            discover_feed(html, &page_url()).map(String::from),
+            Some("https://cdn.example/site/atom.xml".to_string())
+        );
+    }
+
+    #[test]
+    fn pages_without_a_feed_link_discover_nothing() {
+        let html = r#"<html><head><link rel="alternate" type="text/html" href="/de/"></head>
+            <body><a href="/feed.xml" type="application/rss+xml">RSS</a></body></html>"#;
+        assert_eq!(discover_feed(html, &page_url()), None);
+        assert_eq!(discover_feed("not html at all", &page_url()), None);
+    }
+}
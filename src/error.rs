@@ -8,8 +8,46 @@ pub(crate) type Result<T> = result::Result<T, OpenringError>;
 
 #[derive(Error, Debug, Diagnostic)]
 pub enum OpenringError {
-    #[error("No feed urls were provided. Provide feeds with -s or -S <FILE>.")]
+    #[error("No feed urls were provided. Provide feeds with -s, -S <FILE>, or --opml <FILE>.")]
     FeedMissing,
+    #[error(
+        "No template was provided. Provide one with -t <FILE>, --target, or `template_file` in openring.toml."
+    )]
+    TemplateMissing,
+    #[error("No feeds carry any of the tags given with --tag ({0}).")]
+    #[diagnostic(
+        code(openring::no_tagged_feeds),
+        help("tag feeds with a `tags=...` attribute in the feed list, or drop --tag")
+    )]
+    NoTaggedFeeds(String),
+    #[error("No article can be both at or after {after} and before {before}.")]
+    #[diagnostic(
+        code(openring::empty_date_window),
+        help("check --after, --max-age, and --before; the window they leave is empty")
+    )]
+    EmptyDateWindow { after: String, before: String },
+    #[error("{failed} of {total} feeds failed the health check; at most {allowed} may fail.")]
+    #[diagnostic(
+        code(openring::unhealthy_feeds),
+        help("the report says why each feed failed; --max-failures tolerates more")
+    )]
+    UnhealthyFeeds {
+        failed: usize,
+        total: usize,
+        allowed: usize,
+    },
+    #[error("The cache holds no body for `{0}`.")]
+    #[diagnostic(
+        code(openring::not_cached),
+        help("`openring cache list` shows what the cache holds")
+    )]
+    NotCached(String),
+    #[error("The feed at `{0}` has no cached copy to use offline.")]
+    #[diagnostic(
+        code(openring::not_cached_offline),
+        help("run once with network access to cache it, or drop --offline")
+    )]
+    NotCachedOffline(String),
     #[error("Failed to parse civil date.")]
     CivilDateError(#[from] jiff::Error),
     #[error(transparent)]
@@ -23,18 +61,62 @@ pub enum OpenringError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     FeedWeightError(#[from] FeedWeightError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    FeedAttributeError(#[from] FeedAttributeError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    IncludeError(#[from] IncludeError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ConflictingListingError(#[from] Box<ConflictingListingError>),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TargetError(#[from] TargetError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    OpmlError(#[from] OpmlError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ConfigError(#[from] ConfigError),
     #[error("The feed `{url}` is listed with conflicting weights ({a} and {b}).")]
     #[diagnostic(
         code(openring::conflicting_weight_error),
         help("each feed takes a single weight; keep one of the two")
     )]
     ConflictingWeightError { url: String, a: usize, b: usize },
+    #[error("The feed `{url}` is listed with conflicting {key} values ({a} and {b}).")]
+    #[diagnostic(
+        code(openring::conflicting_attribute_error),
+        help("each feed takes a single {key}; keep one of the two")
+    )]
+    ConflictingAttributeError {
+        url: String,
+        key: &'static str,
+        a: String,
+        b: String,
+    },
     #[error("The feed at `{0}` was empty.")]
     #[diagnostic(code(openring::empty_feed_error))]
     EmptyFeedError(String),
     #[error("The feed at `{url}` is too large ({bytes} bytes).")]
     #[diagnostic(code(openring::feed_too_large_error))]
     FeedTooLargeError { url: String, bytes: u64 },
+    #[error("The {setting} `{value}` cannot be sent in an HTTP header.")]
+    #[diagnostic(
+        code(openring::invalid_header_value),
+        help("leave out control characters such as line breaks")
+    )]
+    InvalidHeaderValue {
+        setting: &'static str,
+        value: String,
+    },
+    #[error("Failed to read root certificates from `{path}`: {reason}.")]
+    #[diagnostic(
+        code(openring::ca_file_error),
+        help("the CA file must hold one or more PEM-encoded certificates")
+    )]
+    CaFileError { path: String, reason: String },
     #[error("The request feed at `{0}` was rate limited (HTTP 429).")]
     #[diagnostic(code(openring::rate_limit_error))]
     RateLimitError(String),
@@ -78,3 +160,90 @@ pub struct FeedWeightError {
     #[help]
     pub help: String,
 }
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Invalid feed attribute.")]
+#[diagnostic(code(openring::feed_attribute_error))]
+pub struct FeedAttributeError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("this attribute is invalid")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Invalid include directive.")]
+#[diagnostic(code(openring::include_error))]
+pub struct IncludeError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("this include failed")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+/// A feed attribute that contradicts the same feed's listing in another
+/// file. The first listing rides along as a related diagnostic, since it
+/// lives in a different source.
+#[derive(Error, Diagnostic, Debug)]
+#[error("Conflicting feed listings across files.")]
+#[diagnostic(code(openring::conflicting_listing_error))]
+pub struct ConflictingListingError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("this value conflicts")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+    #[related]
+    pub first: Vec<FirstListing>,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("The feed was first listed here.")]
+#[diagnostic(severity(Advice))]
+pub struct FirstListing {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("first value")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Invalid render target.")]
+#[diagnostic(code(openring::target_error))]
+pub struct TargetError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Failed to parse OPML file.")]
+#[diagnostic(code(openring::opml_error))]
+pub struct OpmlError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Invalid config file.")]
+#[diagnostic(code(openring::config_error))]
+pub struct ConfigError {
+    #[source_code]
+    pub src: NamedSource<String>,
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
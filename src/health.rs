@@ -0,0 +1,345 @@
+//! The per-feed report of `openring check --fetch`: what each fetch did on
+//! the wire, what the feed holds, and whether it counts as failed.
+
+use std::{io::Write, time::Duration};
+
+use feed_rs::model::Feed;
+use jiff::Timestamp;
+use url::Url;
+
+use crate::{FeedFetch, build_article, error::Result, feedfetcher::FetchTrace, table};
+
+/// One row of the report.
+#[derive(Debug)]
+pub(crate) struct FeedHealth {
+    pub(crate) url: Url,
+    pub(crate) trace: FetchTrace,
+    /// Entries in the feed, usable or not; `None` when no feed was fetched.
+    pub(crate) entries: Option<usize>,
+    /// Entries `render` skips for a missing link, title, or date.
+    pub(crate) skipped: usize,
+    /// The publication date of the newest usable entry.
+    pub(crate) newest: Option<Timestamp>,
+    /// Why the feed counts as failed, if it does.
+    pub(crate) problem: Option<String>,
+}
+
+/// Assess the fetch of every feed in `urls`, in URL order. With
+/// `stale_after`, a feed whose newest entry is older than that as of `now`
+/// counts as failed.
+pub(crate) fn assess_all(
+    urls: &[Url],
+    fetches: Vec<FeedFetch>,
+    stale_after: Option<Duration>,
+    now: Timestamp,
+) -> Vec<FeedHealth> {
+    let stale_before = stale_after.map(|age| now.checked_sub(age).unwrap_or(Timestamp::MIN));
+    let mut report: Vec<FeedHealth> = fetches
+        .into_iter()
+        .map(|(url, result, trace)| assess(url, result, trace, stale_before))
+        .collect();
+    // A fetch task that panicked took its URL with it; the feed must still
+    // show up as failed rather than vanish from the report.
+    for url in urls {
+        if !report.iter().any(|health| &health.url == url) {
+            report.push(FeedHealth {
+                url: url.clone(),
+                trace: FetchTrace::default(),
+                entries: None,
+                skipped: 0,
+                newest: None,
+                problem: Some("the fetch task failed".to_string()),
+            });
+        }
+    }
+    report.sort_unstable_by(|a, b| a.url.cmp(&b.url));
+    report
+}
+
+/// Assess one fetch. Entries are judged the way `render` judges them, so
+/// `skipped` counts exactly the entries a render would drop.
+fn assess(
+    url: Url,
+    result: Result<Feed>,
+    trace: FetchTrace,
+    stale_before: Option<Timestamp>,
+) -> FeedHealth {
+    let feed = match result {
+        Ok(feed) => feed,
+        Err(e) => {
+            return FeedHealth {
+                url,
+                trace,
+                entries: None,
+                skipped: 0,
+                newest: None,
+                problem: Some(e.to_string()),
+            };
+        }
+    };
+
+    let mut skipped = 0;
+    let mut newest: Option<Timestamp> = None;
+    for entry in &feed.entries {
+        // The source title and link only decorate the article; any value
+        // judges the entry the same.
+        match build_article(entry, &url, "", &url) {
+            Ok(Some(article)) => newest = newest.max(Some(article.timestamp)),
+            Ok(None) | Err(_) => skipped += 1,
+        }
+    }
+
+    let problem = match newest {
+        _ if feed.entries.is_empty() => Some("the feed has no entries".to_string()),
+        None => Some("every entry lacks a link, title, or date".to_string()),
+        Some(newest) if stale_before.is_some_and(|cutoff| newest < cutoff) => Some(format!(
+            "the newest entry, from {}, is stale",
+            newest.strftime("%Y-%m-%d")
+        )),
+        Some(_) => None,
+    };
+    FeedHealth {
+        url,
+        trace,
+        entries: Some(feed.entries.len()),
+        skipped,
+        newest,
+        problem,
+    }
+}
+
+/// Write `report` as a table with one row per feed.
+///
+/// # Errors
+///
+/// Returns an error if `out` cannot be written.
+pub(crate) fn write_report(out: impl Write, report: &[FeedHealth]) -> Result<()> {
+    let rows: Vec<[String; 8]> = report.iter().map(row).collect();
+    table::write_table(
+        out,
+        [
+            "RESULT", "FEED", "STATUS", "CACHED", "ENTRIES", "SKIPPED", "NEWEST", "NOTES",
+        ],
+        &rows,
+    )?;
+    Ok(())
+}
+
+fn row(health: &FeedHealth) -> [String; 8] {
+    let dash = || "-".to_string();
+    let mut notes = Vec::new();
+    if let Some(to) = &health.trace.redirected_to {
+        notes.push(format!("redirects to {to}"));
+    }
+    if let Some(feed) = &health.trace.discovered {
+        notes.push(format!("page advertises {feed}"));
+    }
+    if let Some(until) = health.trace.fresh_until {
+        notes.push(format!(
+            "fresh until {}, not requested",
+            until.strftime("%Y-%m-%dT%H:%M:%SZ")
+        ));
+    }
+    if let Some(at) = health.trace.polled_at {
+        notes.push(format!(
+            "polled at {}, within min_interval",
+            at.strftime("%Y-%m-%dT%H:%M:%SZ")
+        ));
+    }
+    if health.trace.retries > 0 {
+        notes.push(format!("retried {} times", health.trace.retries));
+    }
+    if let Some(error) = &health.trace.stale {
+        notes.push(format!("stale copy served: {error}"));
+    }
+    notes.extend(health.problem.clone());
+    [
+        if health.problem.is_some() {
+            "FAIL"
+        } else if health.trace.stale.is_some() {
+            "stale"
+        } else {
+            "ok"
+        }
+        .to_string(),
+        health.url.to_string(),
+        health
+            .trace
+            .status
+            .map_or_else(dash, |status| status.as_u16().to_string()),
+        if health.trace.from_cache { "yes" } else { "no" }.to_string(),
+        health.entries.map_or_else(dash, |n| n.to_string()),
+        health.skipped.to_string(),
+        health
+            .newest
+            .map_or_else(dash, |t| t.strftime("%Y-%m-%d").to_string()),
+        if notes.is_empty() {
+            dash()
+        } else {
+            notes.join("; ")
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::error::OpenringError;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn parse(xml: &str) -> Result<Feed> {
+        Ok(feed_rs::parser::parse(xml.as_bytes())?)
+    }
+
+    const FEED: &str = r#"<?xml version="1.0"?>
+        <feed xmlns="http://www.w3.org/2005/Atom">
+            <title>Example</title>
+            <entry>
+                <title>New</title>
+                <link href="https://a.example/new"/>
+                <published>2024-03-01T00:00:00Z</published>
+            </entry>
+            <entry>
+                <title>Old</title>
+                <link href="https://a.example/old"/>
+                <published>2023-01-01T00:00:00Z</published>
+            </entry>
+            <entry>
+                <title>Undated</title>
+                <link href="https://a.example/undated"/>
+            </entry>
+        </feed>"#;
+
+    #[test]
+    fn counts_entries_skipped_entries_and_the_newest_date() {
+        let health = assess(
+            url("https://a.example/"),
+            parse(FEED),
+            FetchTrace::default(),
+            None,
+        );
+        assert_eq!(health.entries, Some(3));
+        assert_eq!(health.skipped, 1);
+        assert_eq!(health.newest, Some("2024-03-01T00:00:00Z".parse().unwrap()));
+        assert_eq!(health.problem, None);
+    }
+
+    #[test]
+    fn failures_carry_their_reason() {
+        let failed = assess(
+            url("https://a.example/"),
+            Err(OpenringError::RateLimitError(
+                "https://a.example/".to_string(),
+            )),
+            FetchTrace::default(),
+            None,
+        );
+        assert!(
+            failed.problem.as_deref().is_some_and(|p| p.contains("429")),
+            "{:?}",
+            failed.problem
+        );
+
+        let stale = assess(
+            url("https://a.example/"),
+            parse(FEED),
+            FetchTrace::default(),
+            Some("2025-01-01T00:00:00Z".parse().unwrap()),
+        );
+        assert_eq!(
+            stale.problem.as_deref(),
+            Some("the newest entry, from 2024-03-01, is stale")
+        );
+
+        let empty = assess(
+            url("https://a.example/"),
+            parse(r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>E</title></feed>"#),
+            FetchTrace::default(),
+            None,
+        );
+        assert_eq!(empty.problem.as_deref(), Some("the feed has no entries"));
+    }
+
+    #[test]
+    fn missing_fetches_count_as_failed() {
+        let urls = [url("https://b.example/"), url("https://a.example/")];
+        let fetches = vec![(urls[0].clone(), parse(FEED), FetchTrace::default())];
+        let report = assess_all(&urls, fetches, None, Timestamp::UNIX_EPOCH);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].url, urls[1], "sorted by URL");
+        assert!(report[0].problem.is_some());
+        assert!(report[1].problem.is_none());
+    }
+
+    #[test]
+    fn report_has_a_row_per_feed() {
+        let report = [
+            FeedHealth {
+                url: url("https://a.example/feed.xml"),
+                trace: FetchTrace {
+                    status: Some(StatusCode::NOT_MODIFIED),
+                    from_cache: true,
+                    redirected_to: Some(url("https://a.example/atom.xml")),
+                    ..FetchTrace::default()
+                },
+                entries: Some(12),
+                skipped: 0,
+                newest: Some("2024-03-01T00:00:00Z".parse().unwrap()),
+                problem: None,
+            },
+            FeedHealth {
+                url: url("https://b.example/"),
+                trace: FetchTrace {
+                    status: Some(StatusCode::NOT_FOUND),
+                    ..FetchTrace::default()
+                },
+                entries: None,
+                skipped: 0,
+                newest: None,
+                problem: Some("gone".to_string()),
+            },
+            FeedHealth {
+                url: url("https://c.example/"),
+                trace: FetchTrace {
+                    from_cache: true,
+                    stale: Some("server error 503".to_string()),
+                    retries: 2,
+                    ..FetchTrace::default()
+                },
+                entries: Some(3),
+                skipped: 0,
+                newest: Some("2024-02-01T00:00:00Z".parse().unwrap()),
+                problem: None,
+            },
+            FeedHealth {
+                url: url("https://d.example/"),
+                trace: FetchTrace {
+                    from_cache: true,
+                    fresh_until: Some("2024-03-02T12:00:00Z".parse().unwrap()),
+                    ..FetchTrace::default()
+                },
+                entries: Some(1),
+                skipped: 0,
+                newest: Some("2024-03-02T00:00:00Z".parse().unwrap()),
+                problem: None,
+            },
+        ];
+        let mut out = Vec::new();
+        write_report(&mut out, &report).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+RESULT  FEED                        STATUS  CACHED  ENTRIES  SKIPPED  NEWEST      NOTES
+ok      https://a.example/feed.xml  304     yes     12       0        2024-03-01  redirects to https://a.example/atom.xml
+FAIL    https://b.example/          404     no      -        0        -           gone
+stale   https://c.example/          -       yes     3        0        2024-02-01  retried 2 times; stale copy served: server error 503
+ok      https://d.example/          -       yes     1        0        2024-03-02  fresh until 2024-03-02T12:00:00Z, not requested
+"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Limits on the requests a run has in flight: a cap on all of them, a cap
+//! per host, and an optional delay between two requests to the same host.
+
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+/// Cap on in-flight fetches so a long urls file cannot exhaust the process's
+/// file descriptors (macOS defaults to 256 per process). 32 keeps the network
+/// saturated while staying well below that floor.
+pub(crate) const MAX_CONCURRENT_FETCHES: usize = 32;
+
+/// The limits every request of a run shares, feeds and article pages alike.
+#[derive(Debug)]
+pub(crate) struct RequestLimits {
+    global: Arc<Semaphore>,
+    per_host: usize,
+    host_delay: Duration,
+    hosts: DashMap<String, Arc<Host>>,
+}
+
+#[derive(Debug)]
+struct Host {
+    in_flight: Arc<Semaphore>,
+    /// The earliest the next request to this host may start.
+    next_start: Mutex<Instant>,
+}
+
+/// Leave to send one request, held until its response is read.
+#[derive(Debug)]
+pub(crate) struct RequestPermit {
+    _host: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+impl RequestLimits {
+    /// At most `global` requests in flight in all, and `per_host` to any one
+    /// host, zero lifting either cap. Requests to the same host start at
+    /// least `host_delay` apart.
+    pub(crate) fn new(global: usize, per_host: usize, host_delay: Duration) -> Self {
+        let cap = |n: usize| if n == 0 { Semaphore::MAX_PERMITS } else { n };
+        RequestLimits {
+            global: Arc::new(Semaphore::new(cap(global))),
+            per_host: cap(per_host),
+            host_delay,
+            hosts: DashMap::new(),
+        }
+    }
+
+    /// Wait until a request to `url` may start under every limit.
+    ///
+    /// The host's slot and its delay come first, so requests queued or
+    /// paced behind a busy host never tie up slots of the global cap that
+    /// other hosts could use. Hosts are compared by name: `a.github.io` and
+    /// `b.github.io` are two hosts.
+    pub(crate) async fn acquire(&self, url: &Url) -> RequestPermit {
+        let host = Arc::clone(
+            self.hosts
+                .entry(url.host_str().unwrap_or_default().to_string())
+                .or_insert_with(|| {
+                    Arc::new(Host {
+                        in_flight: Arc::new(Semaphore::new(self.per_host)),
+                        next_start: Mutex::new(Instant::now()),
+                    })
+                })
+                .value(),
+        );
+        // acquire_owned errors only when the semaphore is closed, and these
+        // never are.
+        let host_permit = Arc::clone(&host.in_flight)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        if !self.host_delay.is_zero() {
+            // Reserve a start time under the lock and sleep outside it, so
+            // concurrent requests to the host line up one delay apart.
+            let start = {
+                let mut next_start = host
+                    .next_start
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                let start = (*next_start).max(Instant::now());
+                *next_start = start + self.host_delay;
+                start
+            };
+            tokio::time::sleep_until(start.into()).await;
+        }
+        let global_permit = Arc::clone(&self.global)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        RequestPermit {
+            _host: host_permit,
+            _global: global_permit,
+        }
+    }
+}
+
+impl Default for RequestLimits {
+    /// The global cap alone.
+    fn default() -> Self {
+        RequestLimits::new(MAX_CONCURRENT_FETCHES, 0, Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    const BLOCKED: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn caps_requests_per_host() {
+        let limits = RequestLimits::new(MAX_CONCURRENT_FETCHES, 1, Duration::ZERO);
+        let first = limits.acquire(&url("https://a.example/feed.xml")).await;
+
+        let same_host = timeout(BLOCKED, limits.acquire(&url("https://a.example/post"))).await;
+        assert!(same_host.is_err(), "a second request to the host waits");
+        let other_host = timeout(BLOCKED, limits.acquire(&url("https://b.example/"))).await;
+        assert!(other_host.is_ok(), "other hosts are not held up");
+
+        drop(first);
+        let same_host = timeout(BLOCKED, limits.acquire(&url("https://a.example/post"))).await;
+        assert!(same_host.is_ok(), "the slot frees with the first request");
+    }
+
+    #[tokio::test]
+    async fn the_global_cap_spans_hosts() {
+        let limits = RequestLimits::new(1, 0, Duration::ZERO);
+        let _first = limits.acquire(&url("https://a.example/")).await;
+        let other_host = timeout(BLOCKED, limits.acquire(&url("https://b.example/"))).await;
+        assert!(other_host.is_err());
+    }
+
+    #[tokio::test]
+    async fn zero_lifts_a_cap() {
+        let limits = RequestLimits::new(0, 1, Duration::ZERO);
+        let _first = limits.acquire(&url("https://a.example/")).await;
+        let other_host = timeout(BLOCKED, limits.acquire(&url("https://b.example/"))).await;
+        assert!(other_host.is_ok(), "no global cap");
+
+        let limits = RequestLimits::new(MAX_CONCURRENT_FETCHES, 0, Duration::ZERO);
+        let mut permits = Vec::new();
+        for _ in 0..MAX_CONCURRENT_FETCHES {
+            let permit = timeout(BLOCKED, limits.acquire(&url("https://a.example/"))).await;
+            permits.push(permit.expect("only the global cap applies"));
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_to_a_host_start_a_delay_apart() {
+        let delay = Duration::from_millis(100);
+        let limits = RequestLimits::new(MAX_CONCURRENT_FETCHES, 0, delay);
+        let post = url("https://a.example/post");
+        let start = Instant::now();
+        let (a, b, c) = tokio::join!(
+            limits.acquire(&post),
+            limits.acquire(&post),
+            limits.acquire(&post),
+        );
+        assert!(start.elapsed() >= 2 * delay, "{:?}", start.elapsed());
+        drop((a, b, c));
+
+        let start = Instant::now();
+        let _other = limits.acquire(&url("https://b.example/")).await;
+        assert!(start.elapsed() < delay, "other hosts keep their own pace");
+    }
+
+    #[tokio::test]
+    async fn a_paced_host_leaves_the_global_cap_to_others() {
+        let limits = RequestLimits::new(1, 0, Duration::from_secs(10));
+        let post = url("https://a.example/post");
+        drop(limits.acquire(&post).await);
+        // The second request to the host waits out its delay without a
+        // global slot, so the only one stays free for another host.
+        let paced = limits.acquire(&post);
+        let other = async {
+            let other = timeout(BLOCKED, limits.acquire(&url("https://b.example/"))).await;
+            assert!(other.is_ok(), "the other host is not held up");
+        };
+        tokio::select! {
+            _ = paced => panic!("the delay is still running"),
+            () = other => {}
+        }
+    }
+}
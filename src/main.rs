@@ -1,12 +1,15 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use miette::Result;
 use tracing_log::AsTrace;
 
-use openring::{self, args::Args, progress::SuspendingStderr};
+use openring::{self, args::Args, config, progress::SuspendingStderr};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    // Keep the raw matches: layering the config file needs to know which
+    // options were given on the command line and which are clap defaults.
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     tracing_subscriber::fmt()
         .with_env_filter(format!(
@@ -17,6 +20,7 @@ async fn main() -> Result<()> {
         // don't splice on a tty.
         .with_writer(|| SuspendingStderr)
         .init();
+    config::apply(&mut args, &matches)?;
     // `?` converts `OpenringError` into a `miette::Report` via its `Diagnostic`
     // impl, so the `#[diagnostic(code(..))]` codes render (unlike `into_diagnostic`,
     // which discards them).
@@ -0,0 +1,350 @@
+//! Read and write blogrolls as OPML documents, the format feed readers
+//! import and export their subscriptions in.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    num::NonZeroUsize,
+    ops::Range,
+    path::Path,
+};
+
+use miette::NamedSource;
+use roxmltree::{Document, Node, TextPos};
+use url::Url;
+
+use crate::{
+    FeedAttr, FeedAttrs,
+    error::{FeedUrlError, FeedWeightError, OpmlError, Result},
+    record_spanned_feed,
+};
+
+/// Parse an OPML file into feed URLs, each with its optional weight.
+///
+/// Every `<outline>` carrying an `xmlUrl` is a feed; outlines without one
+/// are folders and only contribute their children. An optional `weight`
+/// attribute takes the same positive integer a urls-file line does, and
+/// duplicates merge per [`FeedAttrs::merge`]. The first bad outline fails the
+/// parse with a diagnostic spanning the offending attribute.
+pub(crate) fn parse_opml_file(path: &Path) -> Result<HashMap<Url, FeedAttrs>> {
+    parse_opml(&path.to_string_lossy(), &fs::read_to_string(path)?)
+}
+
+/// [`parse_opml_file`] for an OPML document already in memory, with `name`
+/// as its name in diagnostics.
+pub(crate) fn parse_opml(name: &str, file_src: &str) -> Result<HashMap<Url, FeedAttrs>> {
+    let named = || NamedSource::new(name, file_src.to_owned());
+
+    let doc = Document::parse(file_src).map_err(|e| OpmlError {
+        src: named(),
+        span: offset_of(file_src, e.pos()).into(),
+        help: e.to_string(),
+    })?;
+    let root = doc.root_element();
+    if !root.has_tag_name("opml") {
+        return Err(OpmlError {
+            src: named(),
+            span: start_tag(root).into(),
+            help: format!(
+                "expected an <opml> document, found <{}>",
+                root.tag_name().name()
+            ),
+        }
+        .into());
+    }
+
+    let mut feeds: HashMap<Url, FeedAttrs> = HashMap::new();
+    for outline in root.descendants().filter(|n| n.has_tag_name("outline")) {
+        let Some(xml_url) = outline.attribute_node("xmlUrl") else {
+            // A typed feed outline with nowhere to fetch from is a broken
+            // export, not a folder; everything else untyped is a folder.
+            if let Some(kind) = outline
+                .attribute_node("type")
+                .filter(|t| is_feed_type(t.value()))
+            {
+                return Err(FeedUrlError {
+                    src: named(),
+                    span: kind.range_value().into(),
+                    help: "a feed outline needs an `xmlUrl` attribute".to_string(),
+                }
+                .into());
+            }
+            continue;
+        };
+        let url = Url::parse(xml_url.value().trim()).map_err(|e| FeedUrlError {
+            src: named(),
+            span: xml_url.range_value().into(),
+            help: e.to_string(),
+        })?;
+
+        let mut attrs = Vec::new();
+        if let Some(attr) = outline.attribute_node("weight") {
+            let w = attr
+                .value()
+                .trim()
+                .parse::<NonZeroUsize>()
+                .map_err(|e| FeedWeightError {
+                    src: named(),
+                    span: attr.range_value().into(),
+                    help: format!(
+                        "the weight attribute must be a positive integer ({e}); omit it for the default unweighted behavior"
+                    ),
+                })?;
+            attrs.push((FeedAttr::Weight(w), attr.range_value()));
+        }
+        record_spanned_feed(&mut feeds, url, attrs, named, 0)?;
+    }
+    Ok(feeds)
+}
+
+/// One feed as written to an exported blogroll.
+#[derive(Debug)]
+pub(crate) struct Outline {
+    pub(crate) xml_url: Url,
+    pub(crate) title: String,
+    /// The feed's homepage, when it was fetched and declares one.
+    pub(crate) html_url: Option<Url>,
+    pub(crate) weight: Option<NonZeroUsize>,
+}
+
+/// Write `outlines` to `w` as an OPML 2.0 document, in the order given.
+///
+/// Weights go in the same `weight` attribute [`parse_opml_file`] reads, so
+/// an exported blogroll works as `--opml` input unchanged.
+pub(crate) fn write_opml(mut w: impl Write, title: &str, outlines: &[Outline]) -> io::Result<()> {
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, r#"<opml version="2.0">"#)?;
+    writeln!(w, "  <head>")?;
+    writeln!(w, "    <title>{}</title>", html_escape::encode_text(title))?;
+    writeln!(w, "  </head>")?;
+    writeln!(w, "  <body>")?;
+    for outline in outlines {
+        let title = attr(&outline.title);
+        write!(
+            w,
+            r#"    <outline type="rss" text="{title}" title="{title}" xmlUrl="{}""#,
+            attr(outline.xml_url.as_str())
+        )?;
+        if let Some(html_url) = &outline.html_url {
+            write!(w, r#" htmlUrl="{}""#, attr(html_url.as_str()))?;
+        }
+        if let Some(weight) = outline.weight {
+            write!(w, r#" weight="{weight}""#)?;
+        }
+        writeln!(w, "/>")?;
+    }
+    writeln!(w, "  </body>")?;
+    writeln!(w, "</opml>")?;
+    w.flush()
+}
+
+/// `value` escaped for a double-quoted XML attribute. Control characters,
+/// which XML 1.0 cannot carry at all, become spaces.
+fn attr(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    html_escape::encode_double_quoted_attribute(&cleaned).into_owned()
+}
+
+/// Whether an outline `type` names a feed. OPML 2.0 says `rss`, but exports
+/// in the wild also write `atom`.
+fn is_feed_type(kind: &str) -> bool {
+    kind.eq_ignore_ascii_case("rss") || kind.eq_ignore_ascii_case("atom")
+}
+
+/// The byte range of an element's start tag, so a diagnostic labels the tag
+/// rather than everything nested inside it.
+fn start_tag(node: Node) -> Range<usize> {
+    let range = node.range();
+    let end = node
+        .first_child()
+        .map_or(range.end, |child| child.range().start);
+    range.start..end
+}
+
+/// The byte offset of a parser-reported row and column (both 1-based, the
+/// column counted in characters), clamped to the end of `src`.
+fn offset_of(src: &str, pos: TextPos) -> usize {
+    let line_start: usize = src
+        .split_inclusive('\n')
+        .take(pos.row.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    src[line_start..]
+        .char_indices()
+        .nth(pos.col.saturating_sub(1) as usize)
+        .map_or(src.len(), |(i, _)| line_start + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Write, num::NonZeroUsize};
+
+    use url::Url;
+
+    use super::{Outline, parse_opml_file, write_opml};
+    use crate::error::OpenringError;
+
+    fn opml_file(body: &str) -> tempfile::NamedTempFile {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            tmp,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0"><head><title>Blogroll</title></head><body>{body}</body></opml>"#
+        )
+        .unwrap();
+        tmp
+    }
+
+    #[test]
+    fn parse_opml_reads_feed_outlines_and_weights() {
+        let tmp = opml_file(
+            r#"<outline text="Friends">
+                <outline type="rss" text="A" xmlUrl="https://a.example/feed.xml" weight="7"/>
+                <outline type="rss" text="B" xmlUrl="https://b.example/feed.xml?x=1&amp;y=2"/>
+            </outline>"#,
+        );
+
+        let parsed = parse_opml_file(tmp.path()).unwrap();
+
+        let expected = HashMap::from([
+            (
+                Url::parse("https://a.example/feed.xml").unwrap(),
+                NonZeroUsize::new(7),
+            ),
+            // Attribute values are unescaped before they are parsed.
+            (
+                Url::parse("https://b.example/feed.xml?x=1&y=2").unwrap(),
+                None,
+            ),
+        ]);
+        assert_eq!(crate::tests::weights(parsed), expected);
+    }
+
+    #[test]
+    fn parse_opml_diagnostic_points_at_the_bad_xml_url() {
+        let tmp = opml_file(r#"<outline type="rss" xmlUrl="not a url"/>"#);
+        let src = std::fs::read_to_string(tmp.path()).unwrap();
+
+        let err = parse_opml_file(tmp.path()).unwrap_err();
+        let OpenringError::FeedUrlError(e) = err else {
+            panic!("expected FeedUrlError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), src.find("not a url").unwrap());
+        assert_eq!(e.span.len(), "not a url".len());
+    }
+
+    #[test]
+    fn parse_opml_diagnostic_points_at_the_bad_weight() {
+        let tmp = opml_file(r#"<outline type="rss" xmlUrl="https://a.example/" weight="0"/>"#);
+        let src = std::fs::read_to_string(tmp.path()).unwrap();
+
+        let err = parse_opml_file(tmp.path()).unwrap_err();
+        let OpenringError::FeedWeightError(e) = err else {
+            panic!("expected FeedWeightError, got {err:?}");
+        };
+        assert_eq!(e.span.offset(), src.find("\"0\"").unwrap() + 1);
+        assert_eq!(e.span.len(), 1);
+    }
+
+    #[test]
+    fn parse_opml_rejects_conflicting_duplicate_weights() {
+        let tmp = opml_file(
+            r#"<outline type="rss" xmlUrl="https://a.example/" weight="3"/>
+               <outline type="rss" xmlUrl="https://a.example/" weight="7"/>"#,
+        );
+
+        let err = parse_opml_file(tmp.path()).unwrap_err();
+        let OpenringError::FeedWeightError(e) = err else {
+            panic!("expected FeedWeightError, got {err:?}");
+        };
+        assert!(e.help.contains('3'), "help: {}", e.help);
+    }
+
+    #[test]
+    fn parse_opml_rejects_feed_outlines_without_a_url() {
+        let tmp = opml_file(r#"<outline type="rss" text="Lost"/>"#);
+        assert!(matches!(
+            parse_opml_file(tmp.path()),
+            Err(OpenringError::FeedUrlError(_))
+        ));
+    }
+
+    #[test]
+    fn parse_opml_reports_malformed_xml_at_its_position() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        write!(tmp, "<opml>\n<body><outline></body></opml>").unwrap();
+
+        let err = parse_opml_file(tmp.path()).unwrap_err();
+        let OpenringError::OpmlError(e) = err else {
+            panic!("expected OpmlError, got {err:?}");
+        };
+        // The error lands on the second line, not at the start of the file.
+        assert!(e.span.offset() > "<opml>\n".len(), "span: {:?}", e.span);
+    }
+
+    #[test]
+    fn parse_opml_rejects_documents_that_are_not_opml() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        write!(tmp, "<rss version=\"2.0\"><channel/></rss>").unwrap();
+        assert!(matches!(
+            parse_opml_file(tmp.path()),
+            Err(OpenringError::OpmlError(_))
+        ));
+    }
+
+    // An exported blogroll must read back as input with every feed and
+    // weight intact, whatever its titles hold.
+    #[test]
+    fn written_opml_reads_back_as_input() {
+        let outlines = [
+            Outline {
+                xml_url: Url::parse("https://a.example/feed.xml?x=1&y=2").unwrap(),
+                title: "Tom & \"Jerry\" <3\tforever".to_string(),
+                html_url: Some(Url::parse("https://a.example/").unwrap()),
+                weight: NonZeroUsize::new(7),
+            },
+            Outline {
+                xml_url: Url::parse("https://b.example/atom.xml").unwrap(),
+                title: "b.example".to_string(),
+                html_url: None,
+                weight: None,
+            },
+        ];
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        write_opml(&mut tmp, "Blogroll", &outlines).unwrap();
+
+        let parsed = parse_opml_file(tmp.path()).unwrap();
+
+        let expected: HashMap<Url, Option<NonZeroUsize>> = outlines
+            .into_iter()
+            .map(|o| (o.xml_url, o.weight))
+            .collect();
+        assert_eq!(crate::tests::weights(parsed), expected);
+    }
+
+    #[test]
+    fn written_opml_carries_titles_and_homepages() {
+        let outlines = [Outline {
+            xml_url: Url::parse("https://a.example/feed.xml").unwrap(),
+            title: "A & B".to_string(),
+            html_url: Some(Url::parse("https://a.example/").unwrap()),
+            weight: None,
+        }];
+        let mut out = Vec::new();
+        write_opml(&mut out, "Blogroll", &outlines).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let doc = roxmltree::Document::parse(&out).unwrap();
+        let outline = doc
+            .descendants()
+            .find(|n| n.has_tag_name("outline"))
+            .expect("one outline");
+        assert_eq!(outline.attribute("text"), Some("A & B"));
+        assert_eq!(outline.attribute("htmlUrl"), Some("https://a.example/"));
+        assert_eq!(outline.attribute("weight"), None);
+    }
+}
@@ -1,15 +1,26 @@
 //! Derive a summary for an article whose feed provided none, by fetching the
 //! article's own page and reading the page's description of itself.
 
-use reqwest::{Client, header::CONTENT_TYPE};
+use jiff::Timestamp;
+use reqwest::{
+    Client, StatusCode,
+    header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
 use scraper::{Html, Selector};
 use tracing::debug;
 use url::Url;
 
-/// The largest page body worth downloading for a summary. Article pages run
-/// well under 1 MiB of HTML; anything bigger is almost certainly media or a
-/// mislabeled download, and only the head of the page matters here anyway.
-const MAX_PAGE_BYTES: usize = 8 * 1024 * 1024;
+use crate::{
+    cache::{Cache, CacheValue},
+    feedfetcher::{FetchPolicy, logic, normalize_etag, response_fresh_until},
+    limits::RequestPermit,
+};
+
+/// The default largest page body worth downloading for a summary. Article
+/// pages run well under 1 MiB of HTML; anything bigger is almost certainly
+/// media or a mislabeled download, and only the head of the page matters
+/// here anyway.
+pub(crate) const MAX_PAGE_BYTES: u64 = 8 * 1024 * 1024;
 
 /// Cap on a derived summary, in characters. Roughly two or three sentences:
 /// enough to say what the article is about, short enough that a webring
@@ -18,33 +29,90 @@ const MAX_SUMMARY_CHARS: usize = 500;
 
 /// Fetch `url` and derive a summary from the page itself.
 ///
+/// A derived summary is kept in `cache` under the page's URL with the
+/// page's validators, so later runs send a conditional request, or none at
+/// all while the page's `Cache-Control` or `Expires` says it is fresh.
+/// Offline, or when the page cannot be fetched even after the retries
+/// `policy` allows, the cached summary is served as is.
+///
 /// `None` simply means no summary could be derived (network failure, a
 /// non-HTML link, or a page with nothing quotable), which the caller renders
 /// exactly like a feed that offered no summary, so nothing here can fail the
 /// run. The returned text is plain text with HTML special characters
 /// escaped, safe to embed even through the template's `| safe` filter.
-pub(crate) async fn fetch_summary(client: &Client, url: &Url) -> Option<String> {
-    let resp = match client
-        .get(url.clone())
-        .send()
-        .await
-        .and_then(reqwest::Response::error_for_status)
+pub(crate) async fn fetch_summary(
+    client: &Client,
+    cache: &Cache,
+    url: &Url,
+    policy: FetchPolicy,
+) -> Option<String> {
+    let now = Timestamp::now();
+    // Snapshot the entry by value so no DashMap guard is held across an
+    // await point.
+    let cached: Option<CacheValue> = cache
+        .get(url)
+        .filter(|cv| cv.summary.is_some())
+        .map(|cv| cv.value().clone());
+    let cached_summary = cached.as_ref().and_then(|cv| cv.summary.clone());
+    if policy.offline
+        || cached
+            .as_ref()
+            .and_then(|cv| cv.fresh_until)
+            .is_some_and(|until| until > now)
     {
-        Ok(resp) => resp,
+        return cached_summary;
+    }
+
+    let result = request_page(client, url, cached.as_ref(), &policy).await;
+    let (resp, _permit) = match result {
+        Ok(sent) => sent,
         Err(e) => {
             // Only worth a debug line: the article renders fine without a
             // summary, and the feed's own fetch already surfaces dead hosts.
             debug!(url = url.as_str(), error = %e, "could not fetch page to derive a summary");
+            if logic::is_transient_reqwest(&e) {
+                return cached_summary;
+            }
+            // A page the server refuses for good (a 404 or 410, say) takes
+            // its old summary with it. A 429 only asks to come back later.
+            if cached.is_some()
+                && e.status()
+                    .is_some_and(|s| s.is_client_error() && s != StatusCode::TOO_MANY_REQUESTS)
+            {
+                cache.remove(url);
+            }
             return None;
         }
     };
 
-    let content_type = resp
-        .headers()
-        .get(CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        // Owned copy so the borrow on `resp` ends before the body is consumed.
-        .map(str::to_owned);
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            // Owned copies so the borrow on `resp` ends before the body is
+            // consumed.
+            .map(str::to_owned)
+    };
+    let etag = header(ETAG).map(|etag| normalize_etag(&etag));
+    let last_modified = header(LAST_MODIFIED);
+    let content_type = header(CONTENT_TYPE);
+    let fresh_until = response_fresh_until(&resp, now);
+
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        if let Some(mut cv) = cache.get_mut(url) {
+            cv.timestamp = now;
+            cv.fresh_until = fresh_until;
+            // Absent headers leave the stored validators untouched.
+            if etag.is_some() {
+                cv.etag = etag;
+            }
+            if last_modified.is_some() {
+                cv.last_modified = last_modified;
+            }
+        }
+        return cached_summary;
+    }
+
     if !is_html(content_type.as_deref()) {
         debug!(
             url = url.as_str(),
@@ -53,10 +121,75 @@ pub(crate) async fn fetch_summary(client: &Client, url: &Url) -> Option<String>
         return None;
     }
 
-    let body = read_page_capped(url, resp).await?;
+    let body = read_page_capped(url, resp, policy.max_page_bytes).await?;
     // Non-UTF-8 pages are rare enough that lossy decoding is fine: mangled
     // bytes degrade a summary, not the run.
-    extract_summary(&String::from_utf8_lossy(&body))
+    let Some(summary) = extract_summary(&String::from_utf8_lossy(&body)) else {
+        // The page no longer has anything quotable; its old summary, if
+        // any, is stale.
+        if cached.is_some() {
+            cache.remove(url);
+        }
+        return None;
+    };
+    // An article URL that is also a listed feed or page keeps its feed
+    // entry; the summary is simply derived again next run.
+    if !cache
+        .get(url)
+        .is_some_and(|cv| cv.body.is_some() || cv.discovered.is_some())
+    {
+        cache.insert(
+            url.clone(),
+            CacheValue {
+                timestamp: now,
+                retry_after: None,
+                last_modified,
+                etag,
+                body: None,
+                discovered: None,
+                fresh_until,
+                summary: Some(summary.clone()),
+            },
+        );
+    }
+    Some(summary)
+}
+
+/// Request `url`, conditionally on the validators of its `cached` summary,
+/// retrying transient failures as far as `policy` allows. The response
+/// comes with the permit the request went out under, to hold while its body
+/// is read.
+async fn request_page(
+    client: &Client,
+    url: &Url,
+    cached: Option<&CacheValue>,
+    policy: &FetchPolicy,
+) -> reqwest::Result<(reqwest::Response, RequestPermit)> {
+    let send = |()| async {
+        let permit = policy.limits.acquire(url).await;
+        let mut req = client.get(url.clone());
+        if let Some(timeout) = policy.attempt_timeout() {
+            req = req.timeout(timeout);
+        }
+        if let Some(cv) = cached {
+            if let Some(etag) = &cv.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cv.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let result = req
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map(|resp| (resp, permit));
+        ((), result)
+    };
+    let ((), result, _) = policy
+        .with_retries(url, logic::is_retryable_reqwest, (), send)
+        .await;
+    result
 }
 
 /// Whether a Content-Type header names something the HTML extractor can read.
@@ -71,15 +204,16 @@ fn is_html(content_type: Option<&str>) -> bool {
     mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml")
 }
 
-/// Read the response body, giving up once it grows past [`MAX_PAGE_BYTES`].
+/// Read the response body, giving up once it grows past `limit` bytes.
 /// Content-Length cannot be trusted for chunked or compressed responses, so
 /// the cap is enforced on the decoded bytes as they arrive.
-async fn read_page_capped(url: &Url, mut resp: reqwest::Response) -> Option<Vec<u8>> {
+async fn read_page_capped(url: &Url, mut resp: reqwest::Response, limit: u64) -> Option<Vec<u8>> {
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
     let mut body = Vec::new();
     loop {
         match resp.chunk().await {
             Ok(Some(chunk)) => {
-                if body.len().saturating_add(chunk.len()) > MAX_PAGE_BYTES {
+                if body.len().saturating_add(chunk.len()) > limit {
                     debug!(url = url.as_str(), "page too large; no summary derived");
                     return None;
                 }
@@ -353,17 +487,254 @@ mod tests {
             .await;
         let client = crate::feedfetcher::build_client().unwrap();
         let url = Url::parse(&server.uri()).unwrap();
-        assert_eq!(fetch_summary(&client, &url).await, None);
+        assert_eq!(
+            fetch_summary(&client, &Cache::new(), &url, FetchPolicy::default()).await,
+            None
+        );
+    }
+
+    const PAGE: &str =
+        r#"<html><head><meta name="description" content="About the post"></head></html>"#;
+
+    fn html_page() -> wiremock::ResponseTemplate {
+        wiremock::ResponseTemplate::new(200).set_body_raw(PAGE, "text/html")
+    }
+
+    #[tokio::test]
+    async fn derived_summaries_are_cached_and_revalidated() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(html_page().insert_header("ETag", "\"v1\""))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/post", server.uri())).unwrap();
+        let cache = Cache::new();
+
+        for _ in 0..2 {
+            assert_eq!(
+                fetch_summary(&client, &cache, &url, FetchPolicy::default())
+                    .await
+                    .as_deref(),
+                Some("About the post")
+            );
+        }
+        let cv = cache.get(&url).unwrap();
+        assert_eq!(cv.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(cv.body, None, "the page itself is not kept");
+    }
+
+    #[tokio::test]
+    async fn fresh_or_offline_summaries_skip_the_request() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(html_page().insert_header("Cache-Control", "max-age=3600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/post", server.uri())).unwrap();
+        let cache = Cache::new();
+
+        for policy in [
+            FetchPolicy::default(),
+            FetchPolicy::default(),
+            FetchPolicy::offline(),
+        ] {
+            assert_eq!(
+                fetch_summary(&client, &cache, &url, policy)
+                    .await
+                    .as_deref(),
+                Some("About the post")
+            );
+        }
+        // Offline, an uncached page has nothing to offer.
+        let other = Url::parse(&format!("{}/other", server.uri())).unwrap();
+        assert_eq!(
+            fetch_summary(&client, &cache, &other, FetchPolicy::offline()).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_fetches_serve_the_cached_summary() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/post", server.uri())).unwrap();
+        let cache = Cache::new();
+        {
+            let _ok = Mock::given(method("GET"))
+                .respond_with(html_page())
+                .mount_as_scoped(&server)
+                .await;
+            fetch_summary(&client, &cache, &url, FetchPolicy::default()).await;
+        }
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            fetch_summary(&client, &cache, &url, FetchPolicy::default())
+                .await
+                .as_deref(),
+            Some("About the post")
+        );
+    }
+
+    #[tokio::test]
+    async fn removed_pages_drop_the_cached_summary() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let cache = Cache::new();
+        for status in [404, 410] {
+            let url = Url::parse(&format!("{}/post-{status}", server.uri())).unwrap();
+            {
+                let _ok = Mock::given(method("GET"))
+                    .respond_with(html_page())
+                    .mount_as_scoped(&server)
+                    .await;
+                fetch_summary(&client, &cache, &url, FetchPolicy::default()).await;
+            }
+            assert!(cache.contains_key(&url));
+            let _gone = Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(status))
+                .mount_as_scoped(&server)
+                .await;
+
+            assert_eq!(
+                fetch_summary(&client, &cache, &url, FetchPolicy::default()).await,
+                None,
+                "{status}"
+            );
+            assert!(!cache.contains_key(&url), "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn page_fetches_are_retried_on_gateway_errors() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(html_page())
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/post", server.uri())).unwrap();
+        let policy = FetchPolicy {
+            retries: 1,
+            ..FetchPolicy::default()
+        };
+
+        assert_eq!(
+            fetch_summary(&client, &Cache::new(), &url, policy)
+                .await
+                .as_deref(),
+            Some("About the post")
+        );
+    }
+
+    #[tokio::test]
+    async fn the_time_budget_cuts_a_page_fetch_short() {
+        use std::time::{Duration, Instant};
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(html_page().set_delay(Duration::from_secs(10)))
+            .mount(&server)
+            .await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/post", server.uri())).unwrap();
+        let start = Instant::now();
+        let policy = FetchPolicy {
+            retries: 3,
+            deadline: Some(start + Duration::from_millis(300)),
+            ..FetchPolicy::default()
+        };
+
+        assert_eq!(
+            fetch_summary(&client, &Cache::new(), &url, policy).await,
+            None
+        );
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn summaries_never_replace_a_feed_entry() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(html_page())
+            .mount(&server)
+            .await;
+        let client = crate::feedfetcher::build_client().unwrap();
+        let url = Url::parse(&format!("{}/feed", server.uri())).unwrap();
+        let cache = Cache::new();
+        let feed = CacheValue {
+            timestamp: Timestamp::now(),
+            retry_after: None,
+            last_modified: None,
+            etag: None,
+            body: Some(b"<rss/>".to_vec()),
+            discovered: None,
+            fresh_until: None,
+            summary: None,
+        };
+        cache.insert(url.clone(), feed.clone());
+
+        assert_eq!(
+            fetch_summary(&client, &cache, &url, FetchPolicy::default())
+                .await
+                .as_deref(),
+            Some("About the post")
+        );
+        assert_eq!(*cache.get(&url).unwrap(), feed);
     }
 
     // Constructed straight from an http::Response so the cap is exercised
     // without a multi-megabyte transfer over a socket.
     #[tokio::test]
     async fn oversized_pages_are_not_read() {
-        let big = vec![b'x'; MAX_PAGE_BYTES + 1];
+        let big = vec![b'x'; usize::try_from(MAX_PAGE_BYTES).unwrap() + 1];
         let resp = reqwest::Response::from(http::Response::new(big));
         let url = Url::parse("https://example.com/post").unwrap();
-        assert!(read_page_capped(&url, resp).await.is_none());
+        assert!(read_page_capped(&url, resp, MAX_PAGE_BYTES).await.is_none());
     }
 
     // Whatever the page holds, deriving a summary yields text or nothing but
@@ -0,0 +1,67 @@
+//! Plain-text tables for the reports subcommands print.
+
+use std::io::{self, Write};
+
+/// Write `header` and `rows` with each column padded to its widest cell and
+/// two spaces between columns. The last column is ragged, so no line carries
+/// trailing spaces. Cells may hold single spaces, as an HTTP date does, so
+/// rows split into columns on runs of two or more spaces; empty cells should
+/// hold `-` so every row splits into the same columns.
+///
+/// # Errors
+///
+/// Returns an error if `out` cannot be written.
+pub(crate) fn write_table<const N: usize>(
+    mut out: impl Write,
+    header: [&str; N],
+    rows: &[[String; N]],
+) -> io::Result<()> {
+    let mut widths = header.map(|cell| cell.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let header = header.map(str::to_string);
+    for row in std::iter::once(&header).chain(rows) {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            if i + 1 < N {
+                line.extend(std::iter::repeat_n(' ', width - cell.chars().count()));
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_every_column_but_the_last() {
+        let rows = [
+            ["a".to_string(), "wide cell".to_string(), "x".to_string()],
+            [
+                "ü".to_string(),
+                "-".to_string(),
+                "long last cell".to_string(),
+            ],
+        ];
+        let mut out = Vec::new();
+        write_table(&mut out, ["ONE", "TWO", "THREE"], &rows).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+ONE  TWO        THREE
+a    wide cell  x
+ü    -          long last cell
+"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Render targets: the template, output, and caps one run renders with.
+//!
+//! A run fetches every feed once and then renders each target from the same
+//! feeds, so a footer ring, a sidebar, and a reading-list page cost one round
+//! of network traffic instead of three.
+
+use std::path::PathBuf;
+
+use miette::NamedSource;
+
+use crate::{
+    error::{Result, TargetError},
+    tokens_with_offsets, unquote,
+};
+
+/// One template to render and where to write it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Target {
+    pub(crate) template_file: PathBuf,
+    /// `None` writes to the output `run` was handed, i.e. stdout.
+    pub(crate) output: Option<PathBuf>,
+    /// Overrides `--num-articles` for this target.
+    pub(crate) num_articles: Option<usize>,
+    /// Overrides `--per-source` for this target.
+    pub(crate) per_source: Option<usize>,
+}
+
+/// Parse one `--target` value: `TEMPLATE OUTPUT [num_articles=N]
+/// [per_source=N]`, where an OUTPUT of `-` means stdout. Paths containing
+/// spaces go in double quotes, as attribute values in the urls file do.
+///
+/// # Errors
+///
+/// Returns a diagnostic spanning the offending token when the template or
+/// output is missing, a key is unknown, or a count is not a number.
+pub(crate) fn parse_target(raw: &str) -> Result<Target> {
+    let error = |span: std::ops::Range<usize>, help: String| TargetError {
+        src: NamedSource::new("--target", raw.to_owned()),
+        span: span.into(),
+        help,
+    };
+    let tokens = tokens_with_offsets(raw);
+    let path = |i: usize| -> Result<PathBuf> {
+        let &(start, token) = tokens.get(i).ok_or_else(|| {
+            error(
+                raw.len()..raw.len(),
+                "expected `TEMPLATE OUTPUT [num_articles=N] [per_source=N]`, with `-` as OUTPUT for stdout".to_string(),
+            )
+        })?;
+        let path = unquote(token)
+            .map_err(|issue| error(issue.span(start..start + token.len()), issue.help("path")))?;
+        Ok(PathBuf::from(path))
+    };
+    let template_file = path(0)?;
+    let output = path(1)?;
+
+    let mut target = Target {
+        template_file,
+        output: (output.as_os_str() != "-").then_some(output),
+        num_articles: None,
+        per_source: None,
+    };
+    for &(start, token) in tokens.iter().skip(2) {
+        let span = start..start + token.len();
+        let Some((key, value)) = token.split_once('=') else {
+            return Err(error(
+                span,
+                "expected KEY=VALUE, with KEY one of: num_articles, per_source".to_string(),
+            )
+            .into());
+        };
+        let slot = match key {
+            "num_articles" => &mut target.num_articles,
+            "per_source" => &mut target.per_source,
+            _ => {
+                return Err(error(
+                    start..start + key.len(),
+                    format!("unknown target setting `{key}`; expected num_articles or per_source"),
+                )
+                .into());
+            }
+        };
+        let n = value.parse().map_err(|e| {
+            error(
+                start + key.len() + 1..span.end,
+                format!("{key} must be a non-negative integer ({e})"),
+            )
+        })?;
+        *slot = Some(n);
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Target, parse_target};
+
+    #[test]
+    fn parse_target_reads_paths_and_caps() {
+        assert_eq!(
+            parse_target(r#"sidebar.html "out dir/sidebar.html" num_articles=5 per_source=2"#)
+                .unwrap(),
+            Target {
+                template_file: PathBuf::from("sidebar.html"),
+                output: Some(PathBuf::from("out dir/sidebar.html")),
+                num_articles: Some(5),
+                per_source: Some(2),
+            }
+        );
+        assert_eq!(parse_target("footer.html -").unwrap().output, None);
+    }
+
+    #[test]
+    fn parse_target_diagnostics_point_at_the_bad_token() {
+        for (raw, offset, len) in [
+            ("footer.html", 11, 0),
+            ("footer.html - colour=red", 14, 6),
+            ("footer.html - num_articles=many", 27, 4),
+            ("footer.html - 5", 14, 1),
+        ] {
+            let err = parse_target(raw).unwrap_err();
+            let crate::error::OpenringError::TargetError(e) = err else {
+                panic!("expected TargetError for {raw:?}, got {err:?}");
+            };
+            assert_eq!((e.span.offset(), e.span.len()), (offset, len), "{raw:?}");
+        }
+    }
+}
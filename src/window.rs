@@ -0,0 +1,183 @@
+//! The publication window articles must fall in: `--after`, `--before`,
+//! and `--max-age`.
+
+use std::{str::FromStr, time::Duration};
+
+use jiff::{Timestamp, civil::Date, tz::TimeZone};
+
+use crate::error::{OpenringError, Result};
+
+/// One end of the publication window: a calendar date, which starts at
+/// midnight in the system timezone, or an RFC 3339 timestamp, which names
+/// an exact instant wherever the build runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateBound {
+    Date(Date),
+    Timestamp(Timestamp),
+}
+
+impl DateBound {
+    /// The instant this bound starts at.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a date's midnight falls outside the range of the
+    /// system timezone.
+    pub(crate) fn instant(self) -> Result<Timestamp> {
+        match self {
+            DateBound::Date(date) => Ok(date.to_zoned(TimeZone::system())?.timestamp()),
+            DateBound::Timestamp(timestamp) => Ok(timestamp),
+        }
+    }
+}
+
+impl FromStr for DateBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(timestamp) = s.parse::<Timestamp>() {
+            return Ok(DateBound::Timestamp(timestamp));
+        }
+        // jiff reads the date out of any datetime, so only a bare date may
+        // pass here: a datetime without an offset must not silently lose
+        // its time.
+        match s.parse::<Date>() {
+            Ok(date) if date.to_string() == s => Ok(DateBound::Date(date)),
+            _ => Err(format!(
+                "expected a date like 2024-01-31 or a timestamp with an offset like \
+                 2024-01-31T12:00:00Z, not `{s}`"
+            )),
+        }
+    }
+}
+
+/// The half-open range of publication instants an article must fall in:
+/// at or after `after`, and strictly before `before`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DateWindow {
+    pub(crate) after: Option<Timestamp>,
+    pub(crate) before: Option<Timestamp>,
+}
+
+impl DateWindow {
+    /// Resolve the bounds as of `now`. With both `after` and `max_age`, the
+    /// later of the two lower bounds wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a date bound cannot be placed in the system
+    /// timezone, or if the window is empty, which would silently render
+    /// nothing.
+    pub(crate) fn resolve(
+        after: Option<DateBound>,
+        before: Option<DateBound>,
+        max_age: Option<Duration>,
+        now: Timestamp,
+    ) -> Result<Self> {
+        // An age reaching past the earliest representable instant bounds
+        // nothing.
+        let oldest = max_age.map(|age| now.checked_sub(age).unwrap_or(Timestamp::MIN));
+        let after = match (after.map(DateBound::instant).transpose()?, oldest) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let before = before.map(DateBound::instant).transpose()?;
+        if let (Some(a), Some(b)) = (after, before)
+            && a >= b
+        {
+            return Err(OpenringError::EmptyDateWindow {
+                after: a.to_string(),
+                before: b.to_string(),
+            });
+        }
+        Ok(DateWindow { after, before })
+    }
+
+    pub(crate) fn contains(&self, timestamp: Timestamp) -> bool {
+        self.after.is_none_or(|a| a <= timestamp) && self.before.is_none_or(|b| timestamp < b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::{ToSpan, civil::date};
+
+    use super::*;
+
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_dates_and_offset_timestamps() {
+        assert_eq!(
+            "2024-01-31".parse::<DateBound>(),
+            Ok(DateBound::Date(date(2024, 1, 31)))
+        );
+        assert_eq!(
+            "2024-01-31T12:00:00+02:00".parse::<DateBound>(),
+            Ok(DateBound::Timestamp(ts("2024-01-31T10:00:00Z")))
+        );
+        // Without an offset the instant would depend on the build machine.
+        let err = "2024-01-31T12:00:00".parse::<DateBound>().unwrap_err();
+        assert!(err.contains("offset"), "{err}");
+    }
+
+    #[test]
+    fn timestamp_bounds_ignore_the_system_timezone() {
+        let bound = DateBound::Timestamp(ts("2024-01-31T12:00:00Z"));
+        assert_eq!(bound.instant().unwrap(), ts("2024-01-31T12:00:00Z"));
+    }
+
+    #[test]
+    fn window_is_half_open() {
+        let window = DateWindow::resolve(
+            Some(DateBound::Timestamp(ts("2024-01-01T00:00:00Z"))),
+            Some(DateBound::Timestamp(ts("2024-02-01T00:00:00Z"))),
+            None,
+            Timestamp::UNIX_EPOCH,
+        )
+        .unwrap();
+        assert!(window.contains(ts("2024-01-01T00:00:00Z")));
+        assert!(window.contains(ts("2024-01-31T23:59:59Z")));
+        assert!(!window.contains(ts("2024-02-01T00:00:00Z")));
+        assert!(!window.contains(ts("2023-12-31T23:59:59Z")));
+    }
+
+    #[test]
+    fn the_later_lower_bound_wins() {
+        let now = ts("2024-06-01T00:00:00Z");
+        let thirty_days = Duration::from_hours(30 * 24);
+        let recent = DateBound::Timestamp(ts("2024-05-20T00:00:00Z"));
+        let old = DateBound::Timestamp(ts("2023-01-01T00:00:00Z"));
+
+        let window = DateWindow::resolve(Some(recent), None, Some(thirty_days), now).unwrap();
+        assert_eq!(window.after, Some(ts("2024-05-20T00:00:00Z")));
+        let window = DateWindow::resolve(Some(old), None, Some(thirty_days), now).unwrap();
+        assert_eq!(window.after, Some(now - 720.hours()));
+    }
+
+    #[test]
+    fn an_empty_window_is_an_error() {
+        let day = DateBound::Timestamp(ts("2024-01-01T00:00:00Z"));
+        let err = DateWindow::resolve(Some(day), Some(day), None, Timestamp::UNIX_EPOCH);
+        assert!(matches!(err, Err(OpenringError::EmptyDateWindow { .. })));
+
+        // A max age reaching back past --before leaves nothing either.
+        let err = DateWindow::resolve(
+            None,
+            Some(day),
+            Some(Duration::from_mins(1)),
+            ts("2024-06-01T00:00:00Z"),
+        );
+        assert!(matches!(err, Err(OpenringError::EmptyDateWindow { .. })));
+    }
+
+    #[test]
+    fn huge_max_age_bounds_nothing() {
+        let window =
+            DateWindow::resolve(None, None, Some(Duration::MAX), Timestamp::UNIX_EPOCH).unwrap();
+        assert!(window.contains(Timestamp::MIN));
+    }
+}